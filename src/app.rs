@@ -3,27 +3,46 @@
 // Released under the MIT license.
 // see https://opensource.org/licenses/mit-license.php
 
+use crate::config::{Config, DirStyle, KeyType, ViewConfig, ViewSort};
+use crate::header_label::{self, HeaderLabel};
 use crate::key_bind;
-use crate::page::{PageList, SwapDirection};
-use anyhow::Result;
+use crate::page::{self, Page, PageList, Pin, SwapDirection};
+use anyhow::{bail, Context, Result};
+use std::borrow::Cow;
 use std::cmp;
+use std::env;
+use std::fmt;
 use std::fmt::Write as _;
+use std::fs;
 use std::io;
-use std::io::Stdout;
+use std::io::{Stdout, Write as _};
+use std::path::{self, Path, PathBuf};
+use std::process::Command;
 use termion::event::Key;
-use termion::input::TermRead;
 use termion::raw::{IntoRawMode, RawTerminal};
-use termion::screen::{AlternateScreen, IntoAlternateScreen};
+use termion::screen::{AlternateScreen, IntoAlternateScreen, ToAlternateScreen, ToMainScreen};
 use tui::backend::{Backend, TermionBackend};
-use tui::layout::{Alignment, Constraint, Direction, Layout};
-use tui::style::{Modifier, Style};
+use tui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Modifier, Style};
 use tui::terminal::{Frame, Terminal};
-use tui::widgets::{Block, Paragraph, Row, Table, TableState};
+use tui::widgets::{Block, Clear, Paragraph, Row, Table, TableState, Wrap};
 use unicode_width::UnicodeWidthStr;
 
-#[derive(Debug)]
 pub struct Tui<B: Backend> {
     terminal: Terminal<B>,
+
+    /// raw mode of the real terminal, suspended while another program uses it
+    raw_terminal: Option<RawTerminal<Stdout>>,
+}
+
+// `RawTerminal` does not implement `Debug`.
+// `RawTerminal`は`Debug`を実装していない。
+impl<B: Backend + fmt::Debug> fmt::Debug for Tui<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tui")
+            .field("terminal", &self.terminal)
+            .finish_non_exhaustive()
+    }
 }
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -33,36 +52,161 @@ enum Status {
     Picked,
     AskQuit,
     AskSave,
+    AskSaveOutside,
+    Command,
+    Grabbed,
+    Arranging,
+    Note,
     Quit,
 }
 
+/// How a session ended.
+///
+/// セッションの終わり方。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Outcome {
+    Saved,
+    #[default]
+    Discarded,
+}
+
 pub struct App {
     page_list: PageList,
     selected_idx: usize,
     current_status: Status,
     previous_status: Status,
+    header_label: HeaderLabel,
+    assume_yes: bool,
+    live_resort: bool,
+    wrap: bool,
+    display_desc: bool,
+    readonly: bool,
+    emit_script: bool,
+    script: Option<String>,
+    outcome: Outcome,
+    message: Option<String>,
+    command: String,
+    grabbed_idx: usize,
+    marked_idx: Option<usize>,
+    edit_requested: bool,
+    review_idx: usize,
+    note: String,
+    compact: bool,
+    number_titles: bool,
+    picked_symbol: String,
+    unpicked_symbol: String,
+    dir_style: DirStyle,
+    home_dir: Option<PathBuf>,
+    view: ViewConfig,
+    #[cfg(feature = "clipboard")]
+    clipboard: Option<arboard::Clipboard>,
 }
 
-impl Tui<TermionBackend<AlternateScreen<RawTerminal<Stdout>>>> {
+impl Tui<TermionBackend<AlternateScreen<Stdout>>> {
     pub fn try_new() -> Result<Self> {
-        let stdout = io::stdout().into_raw_mode()?.into_alternate_screen()?;
-        let backend = TermionBackend::new(stdout);
+        // Raw mode is held apart from the backend so that it can be suspended for an editor.
+        // エディタのために一時停止できるよう、rawモードはバックエンドとは別に持つ。
+        let raw_terminal = io::stdout().into_raw_mode()?;
+        let stdout = io::stdout().into_alternate_screen()?;
+        let mut tui = Self::with_backend(TermionBackend::new(stdout))?;
+        tui.raw_terminal = Some(raw_terminal);
+        Ok(tui)
+    }
+}
+
+impl<B: Backend> Tui<B> {
+    /// Build a TUI on top of any backend, e.g. `tui::backend::TestBackend`.
+    /// 任意のバックエンド（`tui::backend::TestBackend` など）の上に TUI を作る。
+    pub fn with_backend(backend: B) -> Result<Self> {
         let mut terminal = Terminal::new(backend)?;
         terminal.hide_cursor()?;
-        Ok(Self { terminal })
+        Ok(Self {
+            terminal,
+            raw_terminal: None,
+        })
     }
-    pub fn run(&mut self, app: &mut App) -> Result<()> {
+
+    /// Drive `app` with keys read from `keys` until it quits or the keys run out.
+    /// `keys` から読んだキーで `app` を動かし、終了するかキーが尽きるまで続ける。
+    pub fn run<I>(&mut self, app: &mut App, keys: I) -> Result<Outcome>
+    where
+        I: IntoIterator<Item = io::Result<Key>>,
+    {
         self.terminal.draw(|frame| app.ui(frame))?;
-        while let Some(Ok(key)) = io::stdin().keys().next() {
-            app.transition(key)?;
-            if let Status::Quit = app.current_status {
+        for key in keys {
+            app.transition(key?)?;
+            if app.edit_requested {
+                app.edit_requested = false;
+                let path = app.page_list[app.selected_idx].path().clone();
+                let result = self.suspend(|| open_editor(&path))?;
+                app.finish_edit(result);
+            }
+            if app.is_quit() {
                 break;
             } else {
                 self.terminal.draw(|frame| app.ui(frame))?;
             }
         }
-        Ok(())
+        Ok(app.outcome)
+    }
+
+    /// Give the terminal back to the shell while `f` runs, and redraw everything after it.
+    /// `f`を実行する間は端末をシェルに返し、その後で全体を描き直す。
+    fn suspend<T>(&mut self, f: impl FnOnce() -> T) -> Result<T> {
+        self.terminal.show_cursor()?;
+        if let Some(raw_terminal) = &self.raw_terminal {
+            raw_terminal.suspend_raw_mode()?;
+            write!(io::stdout(), "{}", ToMainScreen)?;
+            io::stdout().flush()?;
+        }
+        let output = f();
+        if let Some(raw_terminal) = &self.raw_terminal {
+            write!(io::stdout(), "{}", ToAlternateScreen)?;
+            io::stdout().flush()?;
+            raw_terminal.activate_raw_mode()?;
+        }
+        self.terminal.hide_cursor()?;
+        self.terminal.clear()?;
+        Ok(output)
+    }
+}
+
+/// Open a file in `$EDITOR` and wait for it to exit. `$EDITOR` may have arguments, e.g. `code --wait`.
+/// `$EDITOR`でファイルを開き、終了を待つ。`$EDITOR`は引数を持ってもよい（`code --wait`など）。
+fn open_editor(path: &Path) -> Result<()> {
+    let editor = env::var("EDITOR").context("EDITOR is not set")?;
+    let mut arg_list = editor.split_whitespace();
+    let program = arg_list.next().context("EDITOR is empty")?;
+    let status = Command::new(program)
+        .args(arg_list)
+        .arg(path)
+        .status()
+        .with_context(|| format!("failed to run {}", editor))?;
+    if !status.success() {
+        bail!("{} exited with {}", editor, status);
+    }
+    Ok(())
+}
+
+/// Count the rows a text takes when wrapped at word boundaries to the given width.
+/// 指定した幅で単語の区切りで折り返したときに、テキストが占める行数を数える。
+fn wrapped_height(text: &str, width: u16) -> u16 {
+    let width = cmp::max(width as usize, 1);
+    let mut row_count = 1;
+    let mut row_width = 0;
+    for word in text.split_whitespace() {
+        let word_width = word.width_cjk();
+        if row_width > 0 && row_width + 1 + word_width <= width {
+            row_width += 1 + word_width;
+        } else {
+            if row_width > 0 {
+                row_count += 1;
+            }
+            row_count += word_width.saturating_sub(1) / width;
+            row_width = (word_width.saturating_sub(1) % width) + 1;
+        }
     }
+    row_count as u16
 }
 
 impl App {
@@ -70,24 +214,143 @@ impl App {
     const DOWN_KEY: Key = Key::Char(key_bind::DOWN);
     const PICK_TOGGLE_KEY: Key = Key::Char(key_bind::PICK_TOGGLE);
     const INCLUDE_TOGGLE_KEY: Key = Key::Char(key_bind::INCLUDE_TOGGLE);
+    const CYCLE_VARIABLE_KEY: Key = Key::Char(key_bind::CYCLE_VARIABLE);
+    const COPY_PATH_KEY: Key = Key::Char(key_bind::COPY_PATH);
+    const SORT_BY_SLUG_KEY: Key = Key::Char(key_bind::SORT_BY_SLUG);
+    const DIRECTORY_TOGGLE_KEY: Key = Key::Char(key_bind::DIRECTORY_TOGGLE);
+    const COMMAND_KEY: Key = Key::Char(key_bind::COMMAND);
+    const GRAB_KEY: Key = Key::Char(key_bind::GRAB);
+    const NOTE_KEY: Key = Key::Char(key_bind::NOTE);
+    const ARRANGE_TOGGLE_KEY: Key = Key::Char(key_bind::ARRANGE_TOGGLE);
+    const MARK_KEY: Key = Key::Char(key_bind::MARK);
+    const EDIT_KEY: Key = Key::Char(key_bind::EDIT);
+    const VIEW_SORT_KEY: Key = Key::Char(key_bind::VIEW_SORT);
+    const NEXT_TIER_KEY: Key = Key::Char(key_bind::NEXT_TIER);
+    const LEGEND_TOGGLE_KEY: Key = Key::Char(key_bind::LEGEND_TOGGLE);
     const QUIT_KEY: Key = Key::Char(key_bind::QUIT);
     const SAVE_KEY: Key = Key::Char(key_bind::SAVE);
 
-    pub fn new(page_list: PageList) -> Self {
+    pub fn new(page_list: PageList, config: &Config) -> Self {
+        // The selection starts at the top of the screen.
+        // 選択は画面の一番上から始める。
+        let selected_idx = if config.display_desc {
+            page_list.len().saturating_sub(1)
+        } else {
+            0
+        };
         Self {
             page_list,
-            selected_idx: 0,
+            selected_idx,
             current_status: Default::default(),
             previous_status: Default::default(),
+            header_label: config.header_label.clone(),
+            assume_yes: config.assume_yes,
+            live_resort: config.live_resort,
+            wrap: config.wrap,
+            display_desc: config.display_desc,
+            readonly: config.readonly,
+            emit_script: config.emit_script,
+            script: None,
+            outcome: Default::default(),
+            message: None,
+            command: String::new(),
+            grabbed_idx: 0,
+            marked_idx: None,
+            edit_requested: false,
+            review_idx: 0,
+            note: String::new(),
+            compact: config.compact,
+            number_titles: config.number_titles,
+            picked_symbol: config.picked_symbol.clone(),
+            unpicked_symbol: config.unpicked_symbol.clone(),
+            dir_style: config.dir_style,
+            home_dir: dirs::home_dir(),
+            view: config.view.clone(),
+            #[cfg(feature = "clipboard")]
+            clipboard: None,
         }
     }
 
+    /// Select the page of a given file. Returns false if it is not listed.
+    /// 与えられたファイルのページを選択する。一覧にない場合はfalseを返す。
+    pub fn select_path(&mut self, path: &Path) -> bool {
+        if let Some(idx) = self.page_list.position_of(path) {
+            self.selected_idx = idx;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Show a message in the status line until the next key is pressed.
+    /// 次のキーが押されるまでステータス行にメッセージを表示する。
+    pub fn set_message(&mut self, message: String) {
+        self.message = Some(message);
+    }
+
+    pub fn page_list(&self) -> &PageList {
+        &self.page_list
+    }
+
+    pub fn selected_idx(&self) -> usize {
+        self.selected_idx
+    }
+
+    /// The patch made on saving with `--emit-script` instead of writing files.
+    /// `--emit-script`でファイルに書き込む代わりに保存時に作るパッチ。
+    pub fn script(&self) -> Option<&str> {
+        self.script.as_deref()
+    }
+
+    pub fn outcome(&self) -> Outcome {
+        self.outcome
+    }
+
+    /// Layout preferences as they are now.
+    /// 現在のレイアウトの設定。
+    pub fn view(&self) -> &ViewConfig {
+        &self.view
+    }
+
+    /// Whether the session has ended.
+    /// セッションが終了したかどうか。
+    pub fn is_quit(&self) -> bool {
+        matches!(self.current_status, Status::Quit)
+    }
+
     fn update_status(&mut self, status: Status) {
+        // Pages may be moved in these states, so the mark would point to another page,
+        // and pages are moved in the order of their values, so the view is no longer sorted by a column.
+        // これらの状態ではページが動きうるため、印が別のページを指してしまう。
+        // また、ページは値の順に動くため、表示を列で並べるのをやめる。
+        if matches!(
+            status,
+            Status::Picked | Status::Command | Status::Grabbed | Status::Arranging
+        ) {
+            self.marked_idx = None;
+            self.view.sort = None;
+        }
+        if let Status::AskSave = status {
+            self.review_idx = 0;
+        }
         self.previous_status = self.current_status;
         self.current_status = status;
     }
 
-    fn transition(&mut self, key: Key) -> Result<()> {
+    /// Advance the state machine by one key press.
+    /// キー入力一つ分だけ状態を進める。
+    pub fn transition(&mut self, key: Key) -> Result<()> {
+        self.message = None;
+        let key = if self.display_desc
+            && self.view.sort.is_none()
+            && matches!(
+                self.current_status,
+                Status::Unpicked | Status::Picked | Status::Grabbed | Status::Arranging
+            ) {
+            Self::flip_vertical(key)
+        } else {
+            key
+        };
         match &self.current_status {
             Status::Unpicked => {
                 self.unpicked(key)?;
@@ -101,13 +364,188 @@ impl App {
             Status::AskSave => {
                 self.ask_save(key)?;
             }
+            Status::AskSaveOutside => {
+                self.ask_save_outside(key)?;
+            }
+            Status::Command => {
+                self.command(key);
+            }
+            Status::Grabbed => {
+                self.grabbed(key);
+            }
+            Status::Arranging => {
+                self.arranging(key);
+            }
+            Status::Note => {
+                self.note(key);
+            }
             Status::Quit => {
                 unreachable!()
             }
         }
+        if self.assume_yes {
+            match self.current_status {
+                Status::AskQuit => self.ask_quit(Key::Char('Y')),
+                Status::AskSave => self.ask_save(Key::Char('Y'))?,
+                _ => (),
+            }
+        }
+        self.clamp_selected_idx();
         Ok(())
     }
 
+    /// Copy the absolute path of the selected file to the clipboard and tell the result.
+    /// 選択中のファイルの絶対パスをクリップボードにコピーし、結果を表示する。
+    fn copy_path(&mut self) {
+        let path = self.page_list[self.selected_idx].path();
+        let path = fs::canonicalize(path)
+            .unwrap_or_else(|_| path.to_owned())
+            .display()
+            .to_string();
+        self.message = Some(match self.set_clipboard_text(path.clone()) {
+            Ok(()) => format!(" Copied {}", path),
+            Err(err) => format!(" Failed to copy: {}", err),
+        });
+    }
+
+    /// Reload the selected page after its file is edited, and tell the result.
+    /// ファイルを編集した後で選択中のページを読み直し、結果を表示する。
+    fn finish_edit(&mut self, result: Result<()>) {
+        let result = result.and_then(|()| self.page_list.reload_page(self.selected_idx));
+        self.message = Some(match result {
+            Ok(()) => format!(
+                " Reloaded {}",
+                self.page_list[self.selected_idx].file_name()
+            ),
+            Err(err) => format!(" {:#}", err),
+        });
+    }
+
+    #[cfg(feature = "clipboard")]
+    fn set_clipboard_text(&mut self, text: String) -> Result<()> {
+        // The clipboard is kept open, since on some platforms its content lives only as long as it.
+        // プラットフォームによってはクリップボードの内容はそれが開いている間しか残らないため、開いたままにする。
+        let clipboard = match &mut self.clipboard {
+            Some(clipboard) => clipboard,
+            None => self.clipboard.insert(arboard::Clipboard::new()?),
+        };
+        clipboard.set_text(text)?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "clipboard"))]
+    fn set_clipboard_text(&mut self, _text: String) -> Result<()> {
+        anyhow::bail!("built without clipboard support")
+    }
+
+    /// Exchange up and down, for the list shown upside down.
+    /// 上下を入れ替える。上下逆に表示された一覧のためのもの。
+    fn flip_vertical(key: Key) -> Key {
+        match key {
+            Self::UP_KEY => Self::DOWN_KEY,
+            Self::DOWN_KEY => Self::UP_KEY,
+            Key::Up => Key::Down,
+            Key::Down => Key::Up,
+            key => key,
+        }
+    }
+
+    /// Position of a page on the screen from its index in the list, or the other way around.
+    /// 一覧での位置から画面上の位置を求める。逆も同じ。
+    fn display_idx(&self, idx: usize) -> usize {
+        if self.display_desc {
+            self.page_list.len().saturating_sub(idx + 1)
+        } else {
+            idx
+        }
+    }
+
+    /// Indices of pages in the order they are shown from the top of the screen.
+    /// 画面の上から表示される順のページの位置。
+    fn view_order(&self) -> Vec<usize> {
+        let mut view_order = (0..self.page_list.len()).collect::<Vec<_>>();
+        // Sorts are stable, so pages with the same text stay in the order of their values.
+        // ソートは安定なため、同じ文字列のページは値の順のままになる。
+        match self.view.sort {
+            Some(ViewSort::Title) => view_order
+                .sort_by(|&a, &b| self.page_list[a].title().cmp(self.page_list[b].title())),
+            Some(ViewSort::File) => view_order.sort_by(|&a, &b| {
+                self.page_list[a]
+                    .file_name()
+                    .cmp(&self.page_list[b].file_name())
+            }),
+            Some(ViewSort::Directory) => view_order.sort_by(|&a, &b| {
+                self.dir_label(&self.page_list[a])
+                    .cmp(&self.dir_label(&self.page_list[b]))
+            }),
+            None if self.display_desc => view_order.reverse(),
+            None => (),
+        }
+        view_order
+    }
+
+    /// Row of a page on the screen.
+    /// 画面上のページの行。
+    fn view_row(&self, idx: usize) -> usize {
+        if self.view.sort.is_none() {
+            return self.display_idx(idx);
+        }
+        self.view_order()
+            .iter()
+            .position(|&view_idx| view_idx == idx)
+            .unwrap_or(0)
+    }
+
+    /// Move the selection to the next row on the screen (or the previous one), in the view sorted by a column.
+    /// 列で並べた表示で、選択を画面上の次の行（または前の行）に動かす。
+    fn step_in_view(&mut self, forward: bool) {
+        let view_order = self.view_order();
+        let row = self.view_row(self.selected_idx);
+        let row = if forward {
+            if row + 1 < view_order.len() {
+                row + 1
+            } else if self.wrap {
+                0
+            } else {
+                row
+            }
+        } else if row != 0 {
+            row - 1
+        } else if self.wrap {
+            view_order.len() - 1
+        } else {
+            row
+        };
+        self.selected_idx = view_order[row];
+    }
+
+    /// Leave a blank line below a row when the row shown below it is in another group.
+    /// Groups are not shown while the view is sorted by a column.
+    /// 下に表示される行が別のグループの場合、行の下に空行を入れる。
+    /// 表示を列で並べている間はグループを示さない。
+    fn group_margin<'a>(&self, idx: usize, row: Row<'a>) -> Row<'a> {
+        if self.view.sort.is_some() {
+            return row;
+        }
+        let below_idx = if self.display_desc {
+            idx.checked_sub(1)
+        } else {
+            Some(idx + 1).filter(|&below_idx| below_idx < self.page_list.len())
+        };
+        match below_idx {
+            Some(below_idx) if !self.page_list.is_same_group(idx, below_idx) => {
+                row.bottom_margin(1)
+            }
+            _ => row,
+        }
+    }
+
+    /// Keep the selection inside the list after an operation that may change its length.
+    /// 一覧の長さが変わりうる操作の後で、選択を一覧の中に保つ。
+    fn clamp_selected_idx(&mut self) {
+        self.selected_idx = cmp::min(self.selected_idx, self.page_list.len().saturating_sub(1));
+    }
+
     fn ui<B: Backend>(&mut self, frame: &mut Frame<B>) {
         match self.current_status {
             Status::Unpicked => {
@@ -122,34 +560,173 @@ impl App {
             Status::AskSave => {
                 self.ui_ask_save(frame);
             }
+            Status::AskSaveOutside => {
+                self.ui_ask_save_outside(frame);
+            }
+            Status::Command => {
+                self.ui_select(frame, false);
+            }
+            Status::Grabbed => {
+                self.ui_select(frame, true);
+            }
+            Status::Arranging => {
+                self.ui_select(frame, true);
+            }
+            Status::Note => {
+                self.ui_select(frame, false);
+            }
             Status::Quit => {
                 unreachable!()
             }
         }
     }
 
+    /// Whether a key edits values or files, which is ignored in read-only mode.
+    /// 値やファイルを編集するキーかどうか。読み取り専用モードでは無視する。
+    fn is_editing_key(key: Key) -> bool {
+        matches!(
+            key,
+            Self::PICK_TOGGLE_KEY
+                | Self::INCLUDE_TOGGLE_KEY
+                | Self::GRAB_KEY
+                | Self::ARRANGE_TOGGLE_KEY
+                | Self::MARK_KEY
+                | Self::COMMAND_KEY
+                | Self::SORT_BY_SLUG_KEY
+                | Self::NEXT_TIER_KEY
+                | Self::EDIT_KEY
+                | Self::SAVE_KEY
+        )
+    }
+
     fn unpicked(&mut self, key: Key) -> Result<()> {
+        if self.readonly {
+            match key {
+                // Nothing can be changed, so there is nothing to confirm.
+                // 何も変更できないため、確認することはない。
+                Self::QUIT_KEY | Key::Esc => {
+                    self.update_status(Status::Quit);
+                    return Ok(());
+                }
+                key if Self::is_editing_key(key) => return Ok(()),
+                _ => (),
+            }
+        }
+        if self.page_list.is_empty() {
+            // Nothing can be selected, so only quitting and saving are handled.
+            // 何も選択できないため、終了と保存だけを扱う。
+            match key {
+                Self::QUIT_KEY | Key::Esc => self.update_status(Status::AskQuit),
+                Self::SAVE_KEY => self.update_status(Status::AskSave),
+                _ => (),
+            }
+            return Ok(());
+        }
         match key {
-            Self::QUIT_KEY => self.update_status(Status::AskQuit),
+            Self::DIRECTORY_TOGGLE_KEY => {
+                self.view.show_directory = !self.view.show_directory;
+            }
+            Self::LEGEND_TOGGLE_KEY => {
+                self.view.show_legend = !self.view.show_legend;
+            }
+            Self::COMMAND_KEY => {
+                self.command.clear();
+                self.update_status(Status::Command);
+            }
+            Self::GRAB_KEY => {
+                self.grabbed_idx = self.selected_idx;
+                self.update_status(Status::Grabbed);
+            }
+            Self::ARRANGE_TOGGLE_KEY => self.update_status(Status::Arranging),
+            Self::MARK_KEY => match self.marked_idx.take() {
+                None => self.marked_idx = Some(self.selected_idx),
+                Some(marked_idx) if marked_idx == self.selected_idx => (),
+                Some(marked_idx) => {
+                    if let Err(err) = self.page_list.swap_pages(marked_idx, self.selected_idx) {
+                        self.message = Some(format!(" {}", err));
+                    }
+                }
+            },
+            Self::NOTE_KEY => {
+                self.note = self.page_list[self.selected_idx]
+                    .note()
+                    .clone()
+                    .unwrap_or_default();
+                self.update_status(Status::Note);
+            }
+            Self::QUIT_KEY | Key::Esc => self.update_status(Status::AskQuit),
             Self::SAVE_KEY => self.update_status(Status::AskSave),
             Self::UP_KEY | Key::Up => {
                 self.update_status(Status::Unpicked);
-                if self.selected_idx != 0 {
+                if self.view.sort.is_some() {
+                    self.step_in_view(false);
+                } else if self.selected_idx != 0 {
                     self.selected_idx -= 1;
+                } else if self.wrap {
+                    self.selected_idx = self.page_list.len() - 1;
                 }
             }
             Self::DOWN_KEY | Key::Down => {
                 self.update_status(Status::Unpicked);
-                if self.selected_idx != self.page_list.len() - 1 {
+                if self.view.sort.is_some() {
+                    self.step_in_view(true);
+                } else if self.selected_idx + 1 < self.page_list.len() {
                     self.selected_idx += 1;
+                } else if self.wrap {
+                    self.selected_idx = 0;
                 }
             }
             Self::INCLUDE_TOGGLE_KEY => {
-                self.page_list.toggle_value(self.selected_idx)?;
+                if let Err(err) = self.page_list.toggle_value(self.selected_idx) {
+                    // The list is left as it was, so the session can go on.
+                    // リストは元のままなので、セッションを続けられる。
+                    self.message = Some(format!(" {}", err));
+                } else if self.live_resort {
+                    self.marked_idx = None;
+                    let path = self.page_list[self.selected_idx].path().clone();
+                    if let Err(err) = self.page_list.sort_and_fix() {
+                        self.message = Some(format!(" {}", err));
+                    }
+                    self.select_path(&path);
+                }
             }
             Self::PICK_TOGGLE_KEY => {
                 self.update_status(Status::Picked);
             }
+            Self::CYCLE_VARIABLE_KEY => {
+                self.marked_idx = None;
+                let path = self.page_list[self.selected_idx].path().clone();
+                if let Err(err) = self.page_list.cycle_key() {
+                    self.message = Some(format!(" {}", err));
+                }
+                self.select_path(&path);
+            }
+            Self::COPY_PATH_KEY => self.copy_path(),
+            Self::EDIT_KEY => self.edit_requested = true,
+            Self::VIEW_SORT_KEY => {
+                self.view.sort = match self.view.sort {
+                    None => Some(ViewSort::Title),
+                    Some(ViewSort::Title) => Some(ViewSort::File),
+                    Some(ViewSort::File) => Some(ViewSort::Directory),
+                    Some(ViewSort::Directory) => None,
+                }
+            }
+            Self::SORT_BY_SLUG_KEY => {
+                self.marked_idx = None;
+                let path = self.page_list[self.selected_idx].path().clone();
+                if let Err(err) = self.page_list.sort_by_slug() {
+                    self.message = Some(format!(" {}", err));
+                }
+                self.select_path(&path);
+            }
+            Self::NEXT_TIER_KEY => {
+                self.marked_idx = None;
+                let path = self.page_list[self.selected_idx].path().clone();
+                if let Err(err) = self.page_list.next_tier(self.selected_idx) {
+                    self.message = Some(format!(" {}", err));
+                }
+                self.select_path(&path);
+            }
             _ => (),
         }
         Ok(())
@@ -160,7 +737,11 @@ impl App {
             Self::QUIT_KEY => self.update_status(Status::AskQuit),
             Self::UP_KEY | Key::Up => {
                 self.update_status(Status::Picked);
-                if self.selected_idx != 0 {
+                if self.selected_idx != 0
+                    && self
+                        .page_list
+                        .is_swappable(self.selected_idx - 1, self.selected_idx)
+                {
                     self.page_list
                         .swap_with_value(self.selected_idx, SwapDirection::Prev)?;
                     self.selected_idx -= 1;
@@ -168,13 +749,17 @@ impl App {
             }
             Self::DOWN_KEY | Key::Down => {
                 self.update_status(Status::Picked);
-                if self.selected_idx != self.page_list.len() - 1 {
+                if self.selected_idx + 1 < self.page_list.len()
+                    && self
+                        .page_list
+                        .is_swappable(self.selected_idx, self.selected_idx + 1)
+                {
                     self.page_list
                         .swap_with_value(self.selected_idx, SwapDirection::Next)?;
                     self.selected_idx += 1;
                 }
             }
-            Self::PICK_TOGGLE_KEY => {
+            Self::PICK_TOGGLE_KEY | Key::Esc => {
                 self.update_status(Status::Unpicked);
             }
             _ => (),
@@ -182,6 +767,131 @@ impl App {
         Ok(())
     }
 
+    /// Move only the cursor while a page is grabbed, and drop the page at the cursor on Enter.
+    /// ページを掴んでいる間はカーソルだけを動かし、Enterでカーソルの位置にページを置く。
+    fn grabbed(&mut self, key: Key) {
+        match key {
+            Self::QUIT_KEY => self.update_status(Status::AskQuit),
+            Self::UP_KEY | Key::Up if self.selected_idx != 0 => {
+                self.selected_idx -= 1;
+            }
+            Self::DOWN_KEY | Key::Down if self.selected_idx + 1 < self.page_list.len() => {
+                self.selected_idx += 1;
+            }
+            Key::Char('\n') => {
+                self.update_status(Status::Unpicked);
+                if let Err(err) = self.page_list.move_to(self.grabbed_idx, self.selected_idx) {
+                    self.selected_idx = self.grabbed_idx;
+                    self.message = Some(format!(" {}", err));
+                }
+            }
+            Key::Esc => {
+                self.update_status(Status::Unpicked);
+                self.selected_idx = self.grabbed_idx;
+            }
+            _ => (),
+        }
+    }
+
+    /// Move the selected page by its position only, leaving every value as it is,
+    /// and renumber pages in the arranged order on Enter (or the toggle key).
+    /// 値には触れずに選択中のページの位置だけを動かし、Enter（または切り替えキー）で並べた順に番号を振り直す。
+    fn arranging(&mut self, key: Key) {
+        match key {
+            Self::QUIT_KEY => self.update_status(Status::AskQuit),
+            Self::UP_KEY | Key::Up
+                if self.selected_idx != 0
+                    && self
+                        .page_list
+                        .is_swappable(self.selected_idx - 1, self.selected_idx) =>
+            {
+                self.page_list
+                    .swap(self.selected_idx - 1, self.selected_idx);
+                self.selected_idx -= 1;
+            }
+            Self::DOWN_KEY | Key::Down
+                if self.selected_idx + 1 < self.page_list.len()
+                    && self
+                        .page_list
+                        .is_swappable(self.selected_idx, self.selected_idx + 1) =>
+            {
+                self.page_list
+                    .swap(self.selected_idx, self.selected_idx + 1);
+                self.selected_idx += 1;
+            }
+            Key::Char('\n') | Self::ARRANGE_TOGGLE_KEY => {
+                self.update_status(Status::Unpicked);
+                let path = self.page_list[self.selected_idx].path().clone();
+                if let Err(err) = self.page_list.renumber_in_place() {
+                    self.message = Some(format!(" {}", err));
+                }
+                self.select_path(&path);
+            }
+            Key::Esc => {
+                // Values are untouched, so sorting by them restores the order before arranging.
+                // 値は変わっていないため、値でソートすれば並べる前の順序に戻る。
+                self.update_status(Status::Unpicked);
+                let path = self.page_list[self.selected_idx].path().clone();
+                if let Err(err) = self.page_list.sort_and_fix() {
+                    self.message = Some(format!(" {}", err));
+                }
+                self.select_path(&path);
+            }
+            _ => (),
+        }
+    }
+
+    /// Edit the note of the selected page, which is attached on Enter and removed if it is empty.
+    /// 選択中のページのメモを編集する。Enterで付け、空ならば取り除く。
+    fn note(&mut self, key: Key) {
+        match key {
+            Key::Char('\n') => {
+                self.update_status(Status::Unpicked);
+                let note = Some(self.note.trim().to_owned()).filter(|note| !note.is_empty());
+                self.page_list[self.selected_idx].set_note(note);
+            }
+            Key::Char(c) => self.note.push(c),
+            Key::Backspace => {
+                self.note.pop();
+            }
+            Key::Esc => self.update_status(Status::Unpicked),
+            _ => (),
+        }
+    }
+
+    /// Read a position typed after `:` and move the selected page there on Enter.
+    /// `:`の後に入力された位置を読み、Enterで選択中のページをそこへ動かす。
+    fn command(&mut self, key: Key) {
+        match key {
+            Key::Char(c) if c.is_ascii_digit() => self.command.push(c),
+            Key::Backspace => {
+                self.command.pop();
+            }
+            Key::Char('\n') => {
+                self.update_status(Status::Unpicked);
+                let result = match self.command.parse::<usize>() {
+                    Ok(position) if position > self.page_list.len() => {
+                        Err(anyhow::anyhow!("position {} is out of the list", position))
+                    }
+                    Ok(position) if position >= 1 => {
+                        // Positions are counted on the screen.
+                        // 位置は画面上で数える。
+                        let new_idx = self.display_idx(position - 1);
+                        self.page_list
+                            .move_to(self.selected_idx, new_idx)
+                            .map(|()| self.selected_idx = new_idx)
+                    }
+                    _ => Err(anyhow::anyhow!("invalid position: {}", self.command)),
+                };
+                if let Err(err) = result {
+                    self.message = Some(format!(" {}", err));
+                }
+            }
+            Key::Esc => self.update_status(Status::Unpicked),
+            _ => (),
+        }
+    }
+
     fn ask_quit(&mut self, key: Key) {
         match key {
             Key::Char('Y') => self.update_status(Status::Quit),
@@ -189,45 +899,190 @@ impl App {
         }
     }
 
+    /// Review the changes to be saved, scrolling them with the up and down keys, and save on `Y`.
+    /// 保存される変更を確認し、上下のキーでスクロールして、`Y`で保存する。
     fn ask_save(&mut self, key: Key) -> Result<()> {
         match key {
-            Key::Char('Y') => {
-                self.page_list.substitute_value();
-                self.page_list.overwrite_frontmatter()?;
-                self.update_status(Status::Quit);
+            Self::UP_KEY | Key::Up => self.review_idx = self.review_idx.saturating_sub(1),
+            Self::DOWN_KEY | Key::Down => {
+                let change_count = self.page_list.pending_changes().len();
+                self.review_idx = cmp::min(self.review_idx + 1, change_count.saturating_sub(1));
             }
+            Key::Char('Y') if self.page_list.outside_change_count() > 0 => {
+                // The status to go back to is kept as it was before asking.
+                // 戻る先の状態は確認の前のままにする。
+                self.current_status = Status::AskSaveOutside;
+            }
+            Key::Char('Y') => self.save()?,
             _ => self.update_status(self.previous_status),
         }
         Ok(())
     }
 
+    /// Confirm again before writing files outside the target directory.
+    /// 対象ディレクトリの外にあるファイルに書き込む前に、もう一度確認する。
+    fn ask_save_outside(&mut self, key: Key) -> Result<()> {
+        match key {
+            Key::Char('Y') => self.save()?,
+            _ => self.update_status(self.previous_status),
+        }
+        Ok(())
+    }
+
+    fn save(&mut self) -> Result<()> {
+        self.page_list.substitute_value()?;
+        if self.emit_script {
+            self.script = Some(self.page_list.patch()?);
+        } else {
+            self.page_list.overwrite_frontmatter()?;
+        }
+        self.outcome = Outcome::Saved;
+        self.update_status(Status::Quit);
+        Ok(())
+    }
+
     fn ui_select<B: Backend>(&self, frame: &mut Frame<B>, picked: bool) {
+        frame.render_widget(Clear, frame.size());
         let guidance = self.guidance(picked);
-        let guidance_height = 1 + (guidance.len() / frame.size().width as usize) as u16;
+        let guidance_height = wrapped_height(&guidance, frame.size().width);
+        let legend = if self.view.show_legend {
+            self.legend()
+        } else {
+            String::new()
+        };
+        let legend_height = if legend.is_empty() {
+            0
+        } else {
+            wrapped_height(&legend, frame.size().width)
+        };
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints(
                 [
                     Constraint::Length(guidance_height),
                     Constraint::Length(1),
-                    Constraint::Length(frame.size().height - guidance_height - 1),
+                    Constraint::Length(legend_height),
+                    Constraint::Length(
+                        frame
+                            .size()
+                            .height
+                            .saturating_sub(guidance_height + 1 + legend_height),
+                    ),
                 ]
                 .as_ref(),
             )
             .split(frame.size());
-        frame.render_widget(Paragraph::new(guidance).block(Block::default()), chunks[0]);
+        frame.render_widget(
+            Paragraph::new(guidance)
+                .wrap(Wrap { trim: true })
+                .block(Block::default()),
+            chunks[0],
+        );
+        frame.render_widget(
+            Paragraph::new(legend)
+                .style(Style::default().add_modifier(Modifier::DIM))
+                .wrap(Wrap { trim: false })
+                .block(Block::default()),
+            chunks[2],
+        );
+        let position = format!(
+            "{}/{} ",
+            cmp::min(self.view_row(self.selected_idx) + 1, self.page_list.len()),
+            self.page_list.len()
+        );
+        let status_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Min(0),
+                Constraint::Length(position.len() as u16),
+            ])
+            .split(chunks[1]);
+        let mut status = String::new();
+        if self.page_list.key_list().len() > 1 {
+            write!(status, " Key: {}", self.page_list.key()).unwrap();
+        }
+        match self.view.sort {
+            Some(ViewSort::Title) => status.push_str(" View: by title"),
+            Some(ViewSort::File) => status.push_str(" View: by file"),
+            Some(ViewSort::Directory) => status.push_str(" View: by directory"),
+            None => (),
+        }
+        if let Some(page) = self.page_list.get(self.selected_idx) {
+            if self.compact {
+                // Paths are not in the table, so the one of the selected page is shown here.
+                // 表にはパスがないため、選択中のページのパスをここに表示する。
+                write!(status, " {}", page.path().display()).unwrap();
+            }
+            let state = if !page.is_in_scope() {
+                if *page.draft() {
+                    "draft"
+                } else {
+                    "out of scope"
+                }
+            } else if page.value().is_some() {
+                "included"
+            } else {
+                "excluded"
+            };
+            if *page.outside_target() {
+                status.push_str(" [outside the target]");
+            } else if *page.symlinked() {
+                status.push_str(" [symbolic link]");
+            }
+            match page.pin() {
+                Some(Pin::First) => write!(status, " ({}, pinned first)", state).unwrap(),
+                Some(Pin::Last) => write!(status, " ({}, pinned last)", state).unwrap(),
+                None => write!(status, " ({})", state).unwrap(),
+            }
+            if let Some(tier_name) = self.page_list.tier_name(page) {
+                write!(status, " Tier: {}", tier_name).unwrap();
+            }
+            if let Some(note) = page.note() {
+                write!(status, " Note: {}", note).unwrap();
+            }
+        } else {
+            status.push_str(" No pages");
+        }
+        if let Some(message) = &self.message {
+            status = message.to_owned();
+        }
+        match self.current_status {
+            Status::Command => status = format!(" Move to: {}", self.command),
+            Status::Note => status = format!(" Note: {}", self.note),
+            Status::Grabbed => {
+                if let Some(page) = self.page_list.get(self.grabbed_idx) {
+                    status = format!(" Grabbed: {}", page.file_name());
+                }
+            }
+            Status::Arranging => status = " Arranging without changing values".to_owned(),
+            _ => (),
+        }
+        frame.render_widget(
+            Paragraph::new(status).block(Block::default()),
+            status_chunks[0],
+        );
+        frame.render_widget(
+            Paragraph::new(position)
+                .alignment(Alignment::Right)
+                .block(Block::default()),
+            status_chunks[1],
+        );
+        if self.compact {
+            self.ui_compact_table(frame, chunks[3], picked);
+            return;
+        }
         let max_file_name_length = self
             .page_list
             .iter()
-            .map(|page| page.path().file_name().unwrap().to_str().unwrap().len())
+            .map(|page| page.file_name().width_cjk())
             .max()
-            .unwrap() as u16;
+            .unwrap_or(0) as u16;
         let max_dir_name_length = self
             .page_list
             .iter()
-            .map(|page| page.path().parent().unwrap().to_str().unwrap().len())
+            .map(|page| self.dir_label(page).width_cjk())
             .max()
-            .unwrap() as u16;
+            .unwrap_or(0) as u16;
         let max_title_name_length = self
             .page_list
             .iter()
@@ -239,35 +1094,86 @@ impl App {
                 }
             })
             .max()
-            .unwrap() as u16;
-        let rows = self.page_list.iter().map(|page| {
-            Row::new(vec![
-                if let Some(title) = page.title() {
-                    title
+            .unwrap_or(0) as u16;
+        // Values put in front of titles are aligned to the right, and integers are padded with zeros.
+        // タイトルの前に置く値は右に揃え、整数は0で埋める。
+        let number_width = if self.number_titles {
+            self.page_list
+                .iter()
+                .filter_map(|page| page.value_text())
+                .map(|value| value.width_cjk())
+                .max()
+                .unwrap_or(0)
+        } else {
+            0
+        };
+        let max_title_name_length = if self.number_titles {
+            max_title_name_length + number_width as u16 + 2
+        } else {
+            max_title_name_length
+        };
+        let column_count = if self.view.show_directory { 6 } else { 5 };
+        let rows = self.view_order().into_iter().map(|idx| {
+            let page = &self.page_list[idx];
+            let title = page.title().as_deref().unwrap_or("");
+            let mut cell_list = vec![
+                if self.number_titles {
+                    let number = match page.value_text() {
+                        Some(text) if *self.page_list.key_type() == KeyType::String => {
+                            format!("{:>width$}", text, width = number_width)
+                        }
+                        Some(text) => format!("{:0>width$}", text, width = number_width),
+                        None => " ".repeat(number_width),
+                    };
+                    Cow::from(format!("{}  {}", number, title))
                 } else {
-                    ""
+                    Cow::from(title)
                 },
-                if page.value().is_none() { "x" } else { "" },
-                page.path().file_name().unwrap().to_str().unwrap(),
-                page.path().parent().unwrap().to_str().unwrap(),
-            ])
+                Cow::from(if !page.is_in_scope() {
+                    "-"
+                } else if page.value().is_none() {
+                    "x"
+                } else if page.pin().is_some() {
+                    "p"
+                } else {
+                    ""
+                }),
+                Cow::from(Self::modified_mark(page)),
+                Cow::from(if page.note().is_some() { "n" } else { "" }),
+                page.file_name(),
+                self.dir_label(page),
+            ];
+            cell_list.truncate(column_count);
+            self.group_margin(idx, self.row_style(idx, Row::new(cell_list)))
         });
-        let header_list = vec!["Title", "", "File", "Dirctory"];
-        let widths = vec![
+        let rows = rows.collect::<Vec<_>>();
+        let mut header_list = vec![
+            self.header_label.title.as_str(),
+            header_label::EXCLUDED,
+            header_label::MODIFIED,
+            header_label::NOTE,
+            self.header_label.file.as_str(),
+            self.header_label.directory.as_str(),
+        ];
+        let mut widths = vec![
             Constraint::Length(cmp::max(
                 max_title_name_length,
-                header_list.get(0).unwrap().len() as u16,
+                self.header_label.title.width_cjk() as u16,
             )),
-            Constraint::Length(1),
+            Constraint::Length(cmp::max(1, header_label::EXCLUDED.width_cjk() as u16)),
+            Constraint::Length(cmp::max(1, header_label::MODIFIED.width_cjk() as u16)),
+            Constraint::Length(cmp::max(1, header_label::NOTE.width_cjk() as u16)),
             Constraint::Length(cmp::max(
                 max_file_name_length,
-                header_list.get(2).unwrap().len() as u16,
+                self.header_label.file.width_cjk() as u16,
             )),
             Constraint::Length(cmp::max(
                 max_dir_name_length,
-                header_list.get(3).unwrap().len() as u16,
+                self.header_label.directory.width_cjk() as u16,
             )),
         ];
+        header_list.truncate(column_count);
+        widths.truncate(column_count);
         let table = Table::new(rows)
             .widths(&widths)
             .header(
@@ -275,10 +1181,143 @@ impl App {
             )
             .column_spacing(2)
             .highlight_style(Style::default().add_modifier(Modifier::BOLD))
-            .highlight_symbol(if picked { " >> " } else { " >  " });
+            .highlight_symbol(self.highlight_symbol(picked));
         let mut table_state = TableState::default();
-        table_state.select(Some(self.selected_idx));
-        frame.render_stateful_widget(table.block(Block::default()), chunks[2], &mut table_state);
+        table_state.select(Some(self.view_row(self.selected_idx)));
+        frame.render_stateful_widget(table.block(Block::default()), chunks[3], &mut table_state);
+    }
+
+    /// A line explaining the symbols in front of the selected page, the markers and the styles of rows.
+    /// 選択中のページの前の記号、マーカー、行のスタイルを説明する行。
+    fn legend(&self) -> String {
+        let mut legend = String::from(" Legend:");
+        for (symbol, meaning) in [
+            (self.unpicked_symbol.trim(), "selected"),
+            (self.picked_symbol.trim(), "picked"),
+        ] {
+            if !symbol.is_empty() {
+                write!(legend, " {} {},", symbol, meaning).unwrap();
+            }
+        }
+        legend.push_str(" x excluded (no value), - out of scope, p pinned, * edited, ~ renumbered on load, n note");
+        if self.compact {
+            legend.push_str("; each row shows the value, the markers and the title");
+        } else {
+            legend.push_str("; excluded rows are dimmed and pinned ones italic");
+        }
+        if self.page_list.iter().any(|page| *page.outside_target()) {
+            legend.push_str(", and files outside the target are yellow");
+        }
+        legend
+    }
+
+    fn highlight_symbol(&self, picked: bool) -> &str {
+        if picked {
+            &self.picked_symbol
+        } else {
+            &self.unpicked_symbol
+        }
+    }
+
+    /// Directory of a page for display in the style given with `--dir-style`.
+    /// `--dir-style`で与えられた形式の、表示用のページのディレクトリ。
+    fn dir_label<'a>(&self, page: &'a Page) -> Cow<'a, str> {
+        let dir = page.dir_name();
+        let base = match self.dir_style {
+            DirStyle::Full => return dir,
+            DirStyle::Home => match &self.home_dir {
+                Some(home_dir) => home_dir.to_string_lossy(),
+                None => return dir,
+            },
+            DirStyle::Target => self.page_list.target_dir().to_string_lossy(),
+        };
+        let Some(rest) = page::strip_dir_text(&dir, &base, page::SEPARATOR_LIST) else {
+            return dir;
+        };
+        match (self.dir_style, rest) {
+            (DirStyle::Home, "") => Cow::from("~"),
+            (DirStyle::Home, rest) => Cow::from(format!("~{}{}", path::MAIN_SEPARATOR, rest)),
+            (_, "") => Cow::from("."),
+            (_, rest) => Cow::from(rest.to_owned()),
+        }
+    }
+
+    /// `*` for a page edited in the session, and `~` for one changed only by renumbering on load.
+    /// セッション中に編集されたページは`*`、読み込み時の番号の振り直しだけで変わったページは`~`。
+    fn modified_mark(page: &Page) -> &'static str {
+        if page.is_edited() {
+            "*"
+        } else if page.is_modified() {
+            "~"
+        } else {
+            ""
+        }
+    }
+
+    /// Dim the rows of excluded pages, italicize the rows of pinned pages, color the rows of files outside the target directory, and underline the row of the grabbed page so that it can be told from the cursor.
+    /// 除外されたページの行を暗くし、固定されたページの行を斜体にし、対象ディレクトリの外にあるファイルの行に色を付け、掴んでいるページの行には下線を引いてカーソルと区別できるようにする。
+    fn row_style<'a>(&self, idx: usize, row: Row<'a>) -> Row<'a> {
+        let mut style = Style::default();
+        if let Some(page) = self.page_list.get(idx) {
+            if page.value().is_none() {
+                style = style.add_modifier(Modifier::DIM);
+            }
+            if page.pin().is_some() {
+                style = style.add_modifier(Modifier::ITALIC);
+            }
+            if *page.outside_target() {
+                style = style.fg(Color::Yellow);
+            }
+        }
+        if matches!(self.current_status, Status::Grabbed) && idx == self.grabbed_idx {
+            style = style.add_modifier(Modifier::UNDERLINED);
+        }
+        if self.marked_idx == Some(idx) {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        row.style(style)
+    }
+
+    /// A single column of values and titles, for narrow terminals.
+    /// 狭い端末向けの、値とタイトルだけの1列。
+    fn ui_compact_table<B: Backend>(&self, frame: &mut Frame<B>, area: Rect, picked: bool) {
+        let value_width = self
+            .page_list
+            .iter()
+            .filter_map(|page| *page.value())
+            .max()
+            .map_or(1, |value| value.to_string().len());
+        let rows = self.view_order().into_iter().map(|idx| {
+            let page = &self.page_list[idx];
+            let value = match page.value() {
+                Some(value) => format!("{:0width$}", value, width = value_width),
+                None if !page.is_in_scope() => format!("{:>width$}", "-", width = value_width),
+                None => format!("{:>width$}", "x", width = value_width),
+            };
+            let title = match page.title() {
+                Some(title) => title.to_owned(),
+                None => page.file_name().into_owned(),
+            };
+            let modified = match Self::modified_mark(page) {
+                "" => " ",
+                mark => mark,
+            };
+            let note = if page.note().is_some() { "n" } else { " " };
+            let row = self.row_style(
+                idx,
+                Row::new(vec![format!("{}{}{} {}", value, modified, note, title)]),
+            );
+            self.group_margin(idx, row)
+        });
+        let rows = rows.collect::<Vec<_>>();
+        let widths = [Constraint::Percentage(100)];
+        let table = Table::new(rows)
+            .widths(&widths)
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(self.highlight_symbol(picked));
+        let mut table_state = TableState::default();
+        table_state.select(Some(self.view_row(self.selected_idx)));
+        frame.render_stateful_widget(table.block(Block::default()), area, &mut table_state);
     }
 
     fn ui_ask_quit<B: Backend>(&self, frame: &mut Frame<B>) {
@@ -300,7 +1339,115 @@ impl App {
         frame.render_widget(opening_msg, chunks[2]);
     }
 
+    /// The changed values with their old and new values, above the question to save.
+    /// 変更された値を新旧の値とともに並べ、その上で保存するかを尋ねる。
     fn ui_ask_save<B: Backend>(&self, frame: &mut Frame<B>) {
+        frame.render_widget(Clear, frame.size());
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Min(0),
+            ])
+            .split(frame.size());
+        let change_list = self.page_list.pending_changes();
+        frame.render_widget(
+            Paragraph::new(format!(
+                " Save and quit? Y / [n]  (Up [{}], Down [{}])",
+                key_bind::UP,
+                key_bind::DOWN
+            )),
+            chunks[0],
+        );
+        frame.render_widget(
+            Paragraph::new(format!(
+                " {} value(s) changed, {} file(s) to be written",
+                change_list.len(),
+                self.page_list.rewrite_count()
+            )),
+            chunks[1],
+        );
+        let rows = change_list.iter().map(|change| {
+            Row::new(vec![
+                change.key.to_owned(),
+                Self::change_text(&change.old_value),
+                Self::change_text(&change.new_value),
+                change.path.display().to_string(),
+            ])
+        });
+        let width = |label: &str, text_list: Vec<String>| {
+            Constraint::Length(
+                text_list
+                    .iter()
+                    .map(|text| text.width_cjk())
+                    .chain([label.width_cjk()])
+                    .max()
+                    .unwrap_or(0) as u16,
+            )
+        };
+        let widths = [
+            width(
+                header_label::KEY,
+                change_list
+                    .iter()
+                    .map(|change| change.key.to_owned())
+                    .collect(),
+            ),
+            width(
+                header_label::OLD,
+                change_list
+                    .iter()
+                    .map(|change| Self::change_text(&change.old_value))
+                    .collect(),
+            ),
+            width(
+                header_label::NEW,
+                change_list
+                    .iter()
+                    .map(|change| Self::change_text(&change.new_value))
+                    .collect(),
+            ),
+            width(
+                header_label::PATH,
+                change_list
+                    .iter()
+                    .map(|change| change.path.display().to_string())
+                    .collect(),
+            ),
+        ];
+        let table = Table::new(rows)
+            .widths(&widths)
+            .header(
+                Row::new(vec![
+                    header_label::KEY,
+                    header_label::OLD,
+                    header_label::NEW,
+                    header_label::PATH,
+                ])
+                .style(Style::default().add_modifier(Modifier::UNDERLINED)),
+            )
+            .column_spacing(2)
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(&self.unpicked_symbol);
+        let mut table_state = TableState::default();
+        if !change_list.is_empty() {
+            table_state.select(Some(self.review_idx));
+        }
+        frame.render_stateful_widget(table.block(Block::default()), chunks[2], &mut table_state);
+    }
+
+    /// A value in the review of changes: `-` when a page has none.
+    /// 変更の確認での値。値がなければ`-`。
+    fn change_text(value: &serde_json::Value) -> String {
+        match value {
+            serde_json::Value::Null => "-".to_owned(),
+            serde_json::Value::String(string) => string.to_owned(),
+            value => value.to_string(),
+        }
+    }
+
+    fn ui_ask_save_outside<B: Backend>(&self, frame: &mut Frame<B>) {
         let chunks = Layout::default()
             .margin(1)
             .constraints([
@@ -309,9 +1456,12 @@ impl App {
                 Constraint::Percentage(50),
             ])
             .split(frame.size());
-        let title = Paragraph::new("Save and quit?")
-            .alignment(Alignment::Center)
-            .block(Block::default());
+        let title = Paragraph::new(format!(
+            "{} file(s) outside the target directory will be written. Save them?",
+            self.page_list.outside_change_count()
+        ))
+        .alignment(Alignment::Center)
+        .block(Block::default());
         frame.render_widget(title, chunks[1]);
         let opening_msg = Paragraph::new("Y / [n]")
             .alignment(Alignment::Center)
@@ -321,28 +1471,270 @@ impl App {
 
     fn guidance(&self, picked: bool) -> String {
         let mut guidance = String::new();
+        if let Status::Note = self.current_status {
+            guidance.push_str(" Attach note [Enter], Cancel [Esc]");
+            return guidance;
+        }
         write!(guidance, " Quit [{}]", key_bind::QUIT).unwrap();
+        if self.page_list.is_empty() {
+            // Nothing can be selected, so only quitting and saving are handled.
+            // 何も選択できないため、終了と保存だけを扱う。
+            if self.readonly {
+                guidance.push_str(" (read-only)");
+            } else {
+                write!(guidance, ", Save [{}]", key_bind::SAVE).unwrap();
+            }
+            return guidance;
+        }
         write!(guidance, ", Up [{}]", key_bind::UP).unwrap();
         write!(guidance, ", Down [{}]", key_bind::DOWN).unwrap();
+        if let Status::Grabbed = self.current_status {
+            guidance.push_str(", Drop [Enter], Cancel [Esc]");
+            return guidance;
+        }
+        if let Status::Arranging = self.current_status {
+            guidance.push_str(", Renumber [Enter], Cancel [Esc]");
+            return guidance;
+        }
         if picked {
             write!(guidance, ", Unpick [{}]", key_bind::PICK_TOGGLE).unwrap();
-        } else {
+        } else if !self.readonly {
             write!(guidance, ", Pick [{}]", key_bind::PICK_TOGGLE).unwrap();
-            if self
-                .page_list
-                .get(self.selected_idx)
-                .unwrap()
-                .value()
-                .is_some()
-            {
-                write!(guidance, ", Exclude [{}]", key_bind::INCLUDE_TOGGLE).unwrap();
-            } else {
-                write!(guidance, ", Include [{}]", key_bind::INCLUDE_TOGGLE).unwrap();
+            match self.page_list.get(self.selected_idx) {
+                Some(page)
+                    if !page.is_in_scope()
+                        || page.pin().is_some()
+                        || *self.page_list.key_type() == KeyType::String => {}
+                Some(page) if page.value().is_some() => {
+                    write!(guidance, ", Exclude [{}]", key_bind::INCLUDE_TOGGLE).unwrap()
+                }
+                Some(_) => write!(guidance, ", Include [{}]", key_bind::INCLUDE_TOGGLE).unwrap(),
+                None => (),
             }
         }
+        if !picked && self.page_list.key_list().len() > 1 {
+            write!(guidance, ", Next key [{}]", key_bind::CYCLE_VARIABLE).unwrap();
+        }
         if !picked {
+            if !self.compact {
+                if self.view.show_directory {
+                    write!(
+                        guidance,
+                        ", Hide directory [{}]",
+                        key_bind::DIRECTORY_TOGGLE
+                    )
+                    .unwrap();
+                } else {
+                    write!(
+                        guidance,
+                        ", Show directory [{}]",
+                        key_bind::DIRECTORY_TOGGLE
+                    )
+                    .unwrap();
+                }
+            }
+            if self.view.show_legend {
+                write!(guidance, ", Hide legend [{}]", key_bind::LEGEND_TOGGLE).unwrap();
+            } else {
+                write!(guidance, ", Show legend [{}]", key_bind::LEGEND_TOGGLE).unwrap();
+            }
+            let view_sort = match self.view.sort {
+                None => "title",
+                Some(ViewSort::Title) => "file",
+                Some(ViewSort::File) => "directory",
+                Some(ViewSort::Directory) => "value",
+            };
+            write!(
+                guidance,
+                ", View by {} [{}]",
+                view_sort,
+                key_bind::VIEW_SORT
+            )
+            .unwrap();
+            if self.readonly {
+                write!(guidance, ", Note [{}]", key_bind::NOTE).unwrap();
+                write!(guidance, ", Copy path [{}]", key_bind::COPY_PATH).unwrap();
+                guidance.push_str(" (read-only)");
+                return guidance;
+            }
+            write!(guidance, ", Grab [{}]", key_bind::GRAB).unwrap();
+            write!(guidance, ", Arrange [{}]", key_bind::ARRANGE_TOGGLE).unwrap();
+            if self.marked_idx.is_some() {
+                write!(guidance, ", Swap with marked [{}]", key_bind::MARK).unwrap();
+            } else {
+                write!(guidance, ", Mark [{}]", key_bind::MARK).unwrap();
+            }
+            write!(guidance, ", Note [{}]", key_bind::NOTE).unwrap();
+            write!(guidance, ", Move to [{}]", key_bind::COMMAND).unwrap();
+            write!(guidance, ", Sort by slug [{}]", key_bind::SORT_BY_SLUG).unwrap();
+            if !self.page_list.tier_list().is_empty() {
+                write!(guidance, ", Next tier [{}]", key_bind::NEXT_TIER).unwrap();
+            }
+            write!(guidance, ", Copy path [{}]", key_bind::COPY_PATH).unwrap();
+            write!(guidance, ", Edit [{}]", key_bind::EDIT).unwrap();
             write!(guidance, ", Save [{}]", key_bind::SAVE).unwrap();
         }
         guidance
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{config, entry, order, write};
+    use std::fs;
+    use tempfile::TempDir;
+    use tui::backend::TestBackend;
+
+    #[test]
+    fn scripted_keys_move_and_save() {
+        let dir = TempDir::new().unwrap();
+        let path = write(&dir, "a.md", "---\nweight: 0\n---\n");
+        write(&dir, "b.md", "---\nweight: 1\n---\n");
+        let config = config(dir.path(), &[]);
+        let mut app = App::new(PageList::try_new(&config).unwrap(), &config);
+        let mut tui = Tui::with_backend(TestBackend::new(120, 12)).unwrap();
+        let key_list = [
+            App::PICK_TOGGLE_KEY,
+            App::DOWN_KEY,
+            App::PICK_TOGGLE_KEY,
+            App::SAVE_KEY,
+            Key::Char('Y'),
+        ];
+        let outcome = tui.run(&mut app, key_list.map(Ok)).unwrap();
+        assert_eq!(outcome, Outcome::Saved);
+        assert_eq!(app.outcome(), Outcome::Saved);
+        assert_eq!(
+            order(app.page_list()),
+            [entry("b.md", Some(0)), entry("a.md", Some(1))]
+        );
+        assert_eq!(fs::read_to_string(path).unwrap(), "---\nweight: 1\n---\n");
+    }
+
+    #[test]
+    fn tiny_terminal_renders_without_panic() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.md", "---\nweight: 0\n---\n");
+        write(&dir, "b.md", "---\nweight: 1\n---\n");
+        for arg_list in [&[][..], &["--compact"]] {
+            let config = config(dir.path(), arg_list);
+            for (width, height) in [(40, 5), (10, 2), (1, 1)] {
+                let mut app = App::new(PageList::try_new(&config).unwrap(), &config);
+                let mut tui = Tui::with_backend(TestBackend::new(width, height)).unwrap();
+                let key_list = [App::DOWN_KEY, App::PICK_TOGGLE_KEY, App::QUIT_KEY];
+                tui.run(&mut app, key_list.map(Ok)).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn view_sort_is_kept_in_view_preferences() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.md", "---\ntitle: B\nweight: 0\n---\n");
+        write(&dir, "b.md", "---\ntitle: A\nweight: 1\n---\n");
+        let mut config = config(dir.path(), &[]);
+        let mut app = App::new(PageList::try_new(&config).unwrap(), &config);
+        let mut tui = Tui::with_backend(TestBackend::new(80, 12)).unwrap();
+        tui.run(&mut app, [App::VIEW_SORT_KEY, App::QUIT_KEY].map(Ok))
+            .unwrap();
+        assert_eq!(app.view().sort, Some(ViewSort::Title));
+        config.view = app.view().clone();
+        let app = App::new(PageList::try_new(&config).unwrap(), &config);
+        assert_eq!(app.view_order(), [1, 0]);
+    }
+
+    #[test]
+    fn long_guidance_wraps_to_show_every_hint() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.md", "---\nweight: 0\n---\n");
+        let config = config(dir.path(), &[]);
+        let mut app = App::new(PageList::try_new(&config).unwrap(), &config);
+        let mut tui = Tui::with_backend(TestBackend::new(80, 12)).unwrap();
+        tui.run(&mut app, []).unwrap();
+        let guidance = app.guidance(false);
+        let row_count = wrapped_height(&guidance, 80) as usize;
+        assert!(row_count > 1);
+        let shown = tui
+            .terminal
+            .backend()
+            .buffer()
+            .content()
+            .chunks(80)
+            .take(row_count)
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.symbol.as_str())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(
+            shown.split_whitespace().collect::<Vec<_>>(),
+            guidance.split_whitespace().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn wrapped_height_counts_word_wrapped_rows() {
+        assert_eq!(wrapped_height("", 10), 1);
+        assert_eq!(wrapped_height("Quit [q]", 10), 1);
+        assert_eq!(wrapped_height("Quit [q], Save [s]", 10), 2);
+        assert_eq!(wrapped_height("Quit [q], Save [s]", 8), 3);
+        assert_eq!(wrapped_height("abcdefghijkl", 5), 3);
+        assert_eq!(wrapped_height("abc", 0), 3);
+    }
+
+    #[test]
+    fn guidance_offers_include_for_excluded_page() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.md", "---\nweight: 0\n---\n");
+        write(&dir, "b.md", "---\ntitle: B\n---\n");
+        let config = config(dir.path(), &[]);
+        let mut app = App::new(PageList::try_new(&config).unwrap(), &config);
+        assert!(app.guidance(false).contains(", Exclude [x]"));
+        app.transition(App::DOWN_KEY).unwrap();
+        let guidance = app.guidance(false);
+        assert!(guidance.contains(", Include [x]"));
+        assert!(!guidance.contains("Exclude"));
+    }
+
+    #[test]
+    fn guidance_for_empty_list() {
+        let dir = TempDir::new().unwrap();
+        let config = config(dir.path(), &[]);
+        let app = App::new(PageList::try_new(&config).unwrap(), &config);
+        assert_eq!(app.guidance(false), " Quit [q], Save [s]");
+    }
+
+    #[test]
+    fn dir_label_follows_dir_style() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        write(&dir, "sub/a.md", "---\nweight: 0\n---\n");
+        let config = config(dir.path(), &["--recursive"]);
+        let mut app = App::new(PageList::try_new(&config).unwrap(), &config);
+        let page = &app.page_list[0];
+        let sub_dir = dir.path().join("sub");
+        assert_eq!(app.dir_label(page), sub_dir.to_string_lossy());
+        app.dir_style = DirStyle::Target;
+        let page = &app.page_list[0];
+        assert_eq!(app.dir_label(page), "sub");
+        app.dir_style = DirStyle::Home;
+        app.home_dir = Some(dir.path().to_owned());
+        let page = &app.page_list[0];
+        assert_eq!(app.dir_label(page), format!("~{}sub", path::MAIN_SEPARATOR));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn dir_label_keeps_drive_letter() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.md", "---\nweight: 0\n---\n");
+        let config = config(dir.path(), &[]);
+        let app = App::new(PageList::try_new(&config).unwrap(), &config);
+        let label = app.dir_label(&app.page_list[0]).into_owned();
+        assert_eq!(label.chars().nth(1), Some(':'));
+        assert!(label.contains('\\'));
+        assert!(!label.contains('/'));
+    }
+}
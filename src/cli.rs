@@ -3,37 +3,750 @@
 // Released under the MIT license.
 // see https://opensource.org/licenses/mit-license.php
 
-use crate::app::{App, Tui};
+use crate::app::{App, Outcome, Tui};
+use crate::config::{
+    Config, DirStyle, DraftMode, ExtFormat, FormatMode, KeyType, Profile, Tier, Traversal,
+    ViewConfig,
+};
+use crate::header_label::HeaderLabel;
 use crate::page::PageList;
-use anyhow::Result;
-use clap::{Parser, ValueHint};
+use anyhow::{bail, Result};
+use clap::parser::ValueSource;
+use clap::{Arg, CommandFactory, FromArgMatches, Parser, ValueHint};
+use std::env;
+use std::ffi::OsString;
+use std::io;
 use std::path::PathBuf;
+use std::process::ExitCode;
+use termion::input::TermRead;
 
 impl Cli {
-    pub fn run() -> Result<()> {
-        let arg = Cli::parse();
-        let mut app = App::new(PageList::try_new(&arg.key, &arg.target_dir, arg.recursive)?);
+    /// Exit code when quitting without saving.
+    /// 保存せずに終了したときの終了コード。
+    const DISCARDED_EXIT_CODE: u8 = 2;
+
+    pub fn run() -> Result<ExitCode> {
+        let arg = match Cli::parse_with_profile(env::args_os().collect(), Profile::load) {
+            Ok(arg) => arg,
+            Err(err) => match err.downcast::<clap::Error>() {
+                Ok(err) => err.exit(),
+                Err(err) => return Err(err),
+            },
+        };
+        let mut config = arg.config()?;
+        if config.key.is_empty() {
+            let key = PageList::detect_key(&config)?;
+            eprintln!("note: --key is not given, so {} is used", key);
+            config.key = vec![key];
+        }
+        if arg.print_config {
+            print!("{}", toml::to_string(&config)?);
+            return Ok(ExitCode::SUCCESS);
+        }
+        let mut page_list = PageList::try_new(&config)?;
+        for path in page_list.non_utf8_list() {
+            eprintln!(
+                "warning: {} is skipped because it is not valid UTF-8",
+                path.display()
+            );
+        }
+        for reason in page_list.unreadable_list() {
+            eprintln!("warning: {}; the file is skipped", reason);
+        }
+        for page in page_list.iter().filter(|page| *page.extra_frontmatter()) {
+            eprintln!(
+                "warning: {} has more than one front matter block; only the first one is handled",
+                page.path().display()
+            );
+        }
+        let mut duplicated = false;
+        for _ in 0..page_list.key_list().len() {
+            let summary = page_list.duplicate_summary();
+            if !summary.is_empty() {
+                duplicated = true;
+                eprintln!(
+                    "warning: pages share values of {}; they are renumbered in the order found:",
+                    page_list.key()
+                );
+                eprint!("{}", summary);
+            }
+            page_list.cycle_key()?;
+        }
+        let mut below_one = false;
+        for _ in 0..page_list.key_list().len() {
+            let summary = page_list.below_one_summary();
+            if !summary.is_empty() {
+                below_one = true;
+                eprintln!(
+                    "warning: pages have values of {} below 1, which --one-based does not expect; they are placed before 1:",
+                    page_list.key()
+                );
+                eprint!("{}", summary);
+            }
+            page_list.cycle_key()?;
+        }
+        let mut renumbered = false;
+        for _ in 0..page_list.key_list().len() {
+            if let Some(summary) = page_list.load_renumber_summary() {
+                renumbered = true;
+                if config.renumber_on_load {
+                    eprintln!("note: {} (--no-renumber-on-load keeps them)", summary);
+                } else {
+                    eprintln!("note: {}", summary);
+                }
+            }
+            page_list.cycle_key()?;
+        }
+        if arg.shuffle {
+            page_list.shuffle()?;
+            if !arg.dry_run {
+                return arg.save_without_interface(&mut page_list);
+            }
+        }
+        if let Some(field) = &arg.from_date {
+            for path in page_list.order_by_date(field)? {
+                eprintln!(
+                    "warning: {} has no valid {}, so it is placed after the pages having one",
+                    path.display(),
+                    field
+                );
+            }
+            if !arg.dry_run {
+                return arg.save_without_interface(&mut page_list);
+            }
+        }
+        if arg.diff {
+            page_list.substitute_value()?;
+            print!("{}", page_list.patch()?);
+            return Ok(ExitCode::SUCCESS);
+        }
+        if arg.dry_run || arg.print_key {
+            for _ in 0..page_list.key_list().len() {
+                if page_list.key_list().len() > 1 {
+                    println!("# {}", page_list.key());
+                }
+                if arg.print_key {
+                    print!("{}", page_list.current_value_summary());
+                } else {
+                    print!("{}", page_list.order_summary());
+                }
+                page_list.cycle_key()?;
+            }
+            return Ok(ExitCode::SUCCESS);
+        }
+        let unreadable = !page_list.unreadable_list().is_empty();
+        let mut app = App::new(page_list, &config);
+        if unreadable {
+            app.set_message(
+                " Files whose values cannot be read are skipped (see the warning on exit)"
+                    .to_owned(),
+            );
+        } else if duplicated {
+            app.set_message(
+                " Pages share values; they are listed in the order found (see the warning on exit)"
+                    .to_owned(),
+            );
+        } else if below_one {
+            app.set_message(
+                " Pages have values below 1 with --one-based (see the warning on exit)".to_owned(),
+            );
+        } else if renumbered {
+            app.set_message(
+                " Loading renumbered pages marked with ~; saving writes them (see the note on exit)"
+                    .to_owned(),
+            );
+        }
+        if let Some(path) = &config.select {
+            if !app.select_path(path) {
+                eprintln!("warning: {} is not listed", path.display());
+            }
+        } else if config.target.is_file() {
+            app.select_path(&config.target);
+        }
         let mut tui = Tui::try_new()?;
-        tui.run(&mut app)?;
-        Ok(())
+        let outcome = tui.run(&mut app, io::stdin().keys())?;
+        drop(tui);
+        if let Err(err) = app.view().save() {
+            eprintln!("warning: {:#}", err);
+        }
+        if let Some(script) = app.script() {
+            print!("{}", script);
+        }
+        if arg.json_summary {
+            println!(
+                "{}",
+                app.page_list().change_summary(outcome == Outcome::Saved)
+            );
+        }
+        match outcome {
+            Outcome::Saved => Ok(ExitCode::SUCCESS),
+            // Nothing can be saved in read-only mode, so quitting is not a discard.
+            // 読み取り専用モードでは何も保存できないため、終了は破棄ではない。
+            Outcome::Discarded if config.readonly => Ok(ExitCode::SUCCESS),
+            Outcome::Discarded => Ok(ExitCode::from(Self::DISCARDED_EXIT_CODE)),
+        }
+    }
+
+    /// Save pages without opening the interface, printing a patch instead with `--emit-script`,
+    /// and the changes as JSON afterwards with `--json-summary`, as a save in the interface does.
+    /// インターフェースを開かずにページを保存する。`--emit-script`では代わりにパッチを出力し、
+    /// `--json-summary`ではその後に変更をJSONで出力する。インターフェースでの保存と同じ。
+    fn save_without_interface(&self, page_list: &mut PageList) -> Result<ExitCode> {
+        page_list.substitute_value()?;
+        if self.emit_script {
+            print!("{}", page_list.patch()?);
+        } else {
+            page_list.overwrite_frontmatter()?;
+        }
+        if self.json_summary {
+            println!("{}", page_list.change_summary(true));
+        }
+        Ok(ExitCode::SUCCESS)
+    }
+
+    /// Parse command line arguments, taking settings not given on the command line (or in the environment) from the profile given with `--profile`.
+    /// The profile is read by a given function, and errors of clap are returned as they are so that the caller can exit with them.
+    /// コマンドライン引数を解析し、コマンドライン（や環境変数）で与えられていない設定を`--profile`で与えられたプロファイルから取る。
+    /// プロファイルは与えられた関数で読み、clapのエラーは呼び出し元がそれで終了できるようにそのまま返す。
+    fn parse_with_profile<F>(arg_list: Vec<OsString>, load_profile: F) -> Result<Self>
+    where
+        F: FnOnce(&str) -> Result<Profile>,
+    {
+        let command = Cli::command();
+        let matches = command.clone().try_get_matches_from(&arg_list)?;
+        let Some(name) = matches.get_one::<String>("profile") else {
+            return Ok(Cli::from_arg_matches(&matches)?);
+        };
+        let profile = load_profile(name)?;
+        let is_given = |arg: &Arg| {
+            matches!(
+                matches.value_source(arg.get_id().as_str()),
+                Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+            )
+        };
+        let conflicts = |arg_a: &Arg, arg_b: &Arg| {
+            command
+                .get_arg_conflicts_with(arg_a)
+                .into_iter()
+                .any(|arg| arg.get_id() == arg_b.get_id())
+        };
+        let mut full_arg_list = arg_list[..1].to_vec();
+        for (long, setting) in &profile.setting_list {
+            let Some(arg) = command
+                .get_arguments()
+                .find(|arg| arg.get_long() == Some(long.as_str()) && long != "profile")
+            else {
+                bail!("{} in profile {} is not an option", long, name);
+            };
+            if setting.is_array() && arg.get_value_delimiter().is_none() {
+                bail!(
+                    "{} in profile {} takes a single value, not a list",
+                    long,
+                    name
+                );
+            }
+            // A setting is left out if the option, or one conflicting with it (e.g. --no-live-resort for live-resort), is given.
+            // そのオプションか、競合するオプション（live-resortに対する--no-live-resortなど）が与えられた場合は設定を使わない。
+            if is_given(arg)
+                || command.get_arguments().any(|other| {
+                    is_given(other) && (conflicts(arg, other) || conflicts(other, arg))
+                })
+            {
+                continue;
+            }
+            full_arg_list.extend(
+                profile
+                    .arg_list(long, setting)?
+                    .into_iter()
+                    .map(OsString::from),
+            );
+        }
+        full_arg_list.extend_from_slice(&arg_list[1..]);
+        Ok(Cli::try_parse_from(full_arg_list)?)
+    }
+
+    /// Resolve settings for a run.
+    /// 実行のための設定を決める。
+    pub(crate) fn config(&self) -> Result<Config> {
+        let view = if self.reset_view {
+            ViewConfig::default()
+        } else {
+            ViewConfig::load().unwrap_or_else(|err| {
+                eprintln!("warning: {:#}", err);
+                ViewConfig::default()
+            })
+        };
+        Ok(Config {
+            key: self.key.clone(),
+            key_candidates: self.key_candidates.clone(),
+            key_type: self.key_type,
+            target: self.target_dir.clone(),
+            create_target: self.create_target,
+            recursive: self.recursive || self.max_depth.is_some(),
+            max_depth: self.max_depth,
+            traversal: self.traversal,
+            dir_style: self.dir_style,
+            group_by_dir: self.group_by_dir,
+            tier: self.tier.clone(),
+            select: self.select.clone(),
+            assume_yes: self.assume_yes,
+            live_resort: self.live_resort && !self.no_live_resort,
+            wrap: self.wrap,
+            display_desc: self.display_desc,
+            readonly: self.readonly,
+            emit_script: self.emit_script,
+            value_as_string: self.value_as_string,
+            keep_quoted: self.keep_quoted,
+            one_based: self.one_based,
+            clean_excluded: self.clean_excluded,
+            normalize: self.normalize,
+            unassigned_first: self.unassigned_first,
+            renumber_on_load: !self.no_renumber_on_load,
+            compact: self.compact,
+            number_titles: self.number_titles,
+            picked_symbol: self.picked_symbol.clone(),
+            unpicked_symbol: self.unpicked_symbol.clone(),
+            require_key: self.require_key,
+            slug_order: self.slug_order,
+            extension: self.extension.clone(),
+            verbatim_body: self.verbatim_body.clone(),
+            format: self.format,
+            ext_format: self.ext_format.clone(),
+            since: self.since.clone(),
+            limit: self.limit,
+            output_dir: self.output_dir.clone(),
+            filter_tag: self.filter_tag.clone(),
+            skip_draft: self.skip_draft,
+            draft_key: self.draft_key.clone(),
+            from_filename: self.from_filename.clone(),
+            profile: self.profile.clone(),
+            header_label: HeaderLabel::try_new(&self.header_label)?,
+            view,
+        })
     }
 }
 
 #[derive(Parser)]
 #[clap(author, version, about, after_help = concat!("Repository: ", env!("CARGO_PKG_REPOSITORY")))]
 pub struct Cli {
-    #[clap(long, help = "Variables in frontmatters to assign order")]
-    key: String,
+    #[clap(
+        long,
+        env = "ORDER_YAML_KEY",
+        value_delimiter = ',',
+        help = "Variables in frontmatters to assign order (nested keys are separated by dots, e.g. menu.main.weight, multiple keys by commas, and a key limited to a category is given as key@category)"
+    )]
+    key: Vec<String>,
+
+    #[clap(
+        long,
+        value_name = "KEYS",
+        value_delimiter = ',',
+        default_value = "weight,order,menu_order",
+        help = "Keys tried in order when --key is not given; the first one having values in files is used"
+    )]
+    key_candidates: Vec<String>,
+
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = KeyType::Integer,
+        help = "Type of values of keys (string values are only reordered and never renumbered)"
+    )]
+    key_type: KeyType,
 
     #[clap(
         short = 't',
         long = "target",
         value_hint(ValueHint::FilePath),
         default_value = ".",
-        help = "Specify a target directory"
+        help = "Specify a target directory (or a file to list with its siblings)"
     )]
     target_dir: PathBuf,
 
+    #[clap(
+        long,
+        help = "Create a missing target directory and start with an empty list instead of failing"
+    )]
+    create_target: bool,
+
     #[clap(short, long, help = "Handles all files under a target directory")]
     recursive: bool,
+
+    #[clap(
+        long,
+        value_name = "DEPTH",
+        help = "Descend at most this many levels of directories below a target directory (implies --recursive)"
+    )]
+    max_depth: Option<usize>,
+
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = Traversal::DepthFirst,
+        help = "Order to walk directories, which decides the initial order of pages without values"
+    )]
+    traversal: Traversal,
+
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = DirStyle::Full,
+        help = "How directories are shown in the list (files are always handled by their full paths)"
+    )]
+    dir_style: DirStyle,
+
+    #[clap(long, help = "Assign sequential numbers within each directory")]
+    group_by_dir: bool,
+
+    #[clap(
+        long,
+        value_name = "NAME=LABEL",
+        value_delimiter = ',',
+        help = "Rename labels of the table header: title, file or directory (e.g. file=Path)"
+    )]
+    header_label: Vec<String>,
+
+    #[clap(
+        long,
+        value_name = "NAME=BASE",
+        value_delimiter = ',',
+        help = "Tiers of values, each numbered from its base value (e.g. featured=0,normal=100); t in the list moves a page to the next tier"
+    )]
+    tier: Vec<Tier>,
+
+    #[clap(
+        long,
+        value_hint(ValueHint::FilePath),
+        help = "Select a file at startup"
+    )]
+    select: Option<PathBuf>,
+
+    #[clap(
+        short = 'y',
+        long,
+        visible_alias = "yes",
+        help = "Save and quit without confirmation"
+    )]
+    assume_yes: bool,
+
+    #[clap(
+        long,
+        help = "Sort the list again right after including or excluding a page"
+    )]
+    live_resort: bool,
+
+    #[clap(
+        long,
+        conflicts_with = "live_resort",
+        help = "Keep pages where they are after including or excluding them until saving (default)"
+    )]
+    no_live_resort: bool,
+
+    #[clap(
+        long,
+        help = "Move the cursor from one end of the list to the other (pages are never moved across the ends)"
+    )]
+    wrap: bool,
+
+    #[clap(
+        long,
+        help = "Show pages with the largest values at the top (values in files stay ascending)"
+    )]
+    display_desc: bool,
+
+    #[clap(
+        long,
+        conflicts_with = "shuffle",
+        help = "Only browse the list: keys editing values, and saving, are disabled"
+    )]
+    readonly: bool,
+
+    #[clap(
+        long,
+        help = "Write values as strings (e.g. \"3\") instead of integers"
+    )]
+    value_as_string: bool,
+
+    #[clap(
+        long,
+        help = "Write values read as quoted strings (e.g. \"3\") back as strings"
+    )]
+    keep_quoted: bool,
+
+    #[clap(
+        long,
+        help = "Write values to files starting from 1 and read them back as starting from 0 (values shown stay 0-based)"
+    )]
+    one_based: bool,
+
+    #[clap(
+        long,
+        help = "Remove keys left without values (null or commented out) from excluded pages on save"
+    )]
+    clean_excluded: bool,
+
+    #[clap(
+        long,
+        help = "Rewrite the whole front matter of every file on save in a consistent style, dropping comments and anchors"
+    )]
+    normalize: bool,
+
+    #[clap(long, help = "Place pages without values at the top of the list")]
+    unassigned_first: bool,
+
+    #[clap(
+        long,
+        help = "Keep values in files when they are loaded instead of renumbering them from 0, until pages are reordered"
+    )]
+    no_renumber_on_load: bool,
+
+    #[clap(
+        long,
+        help = "Fail listing files without front matter or without values of keys"
+    )]
+    require_key: bool,
+
+    #[clap(long, help = "List pages without values in the order of their slugs")]
+    slug_order: bool,
+
+    #[clap(
+        long = "ext",
+        visible_alias = "only",
+        value_name = "EXT",
+        value_delimiter = ',',
+        default_value = "html,md,mdx",
+        help = "Extensions of files to handle, replacing the default ones (e.g. --only md)"
+    )]
+    extension: Vec<String>,
+
+    #[clap(
+        long,
+        value_name = "EXT",
+        value_delimiter = ',',
+        default_value = "html,mdx",
+        help = "Extensions of files whose bodies are kept verbatim and never looked into (front matter is closed only by ---)"
+    )]
+    verbatim_body: Vec<String>,
+
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = FormatMode::Yaml,
+        help = "Format of front matter of files whose extensions are not given by --ext-format; auto reads each file in the format its opening line shows"
+    )]
+    format: FormatMode,
+
+    #[clap(
+        long,
+        value_name = "EXT=FORMAT",
+        value_delimiter = ',',
+        help = "Formats of front matter of files by their extensions, yaml (between --- lines) or toml (between +++ lines), e.g. md=yaml,toml-post=toml (files of other extensions are read in the format of --format)"
+    )]
+    ext_format: Vec<ExtFormat>,
+
+    #[clap(
+        long,
+        value_name = "DURATION|DATE",
+        help = "Handle only files modified since a time (e.g. 7d, 2h, 2023-01-31)"
+    )]
+    since: Option<String>,
+
+    #[clap(
+        long,
+        value_name = "N",
+        help = "Stop loading files after N pages, e.g. to try a large directory (only they are reordered and saved)"
+    )]
+    limit: Option<usize>,
+
+    #[clap(
+        long,
+        value_name = "DIR",
+        value_hint(ValueHint::DirPath),
+        help = "Write copies of the files to their relative paths under a directory on save, leaving the files untouched"
+    )]
+    output_dir: Option<PathBuf>,
+
+    #[clap(
+        long,
+        value_name = "TAG",
+        help = "Edit only pages having a tag in their tags, and show the others greyed out"
+    )]
+    filter_tag: Option<String>,
+
+    #[clap(
+        long,
+        value_enum,
+        value_name = "MODE",
+        num_args = 0..=1,
+        default_missing_value = "exclude",
+        help = "Leave out pages marked as drafts (exclude), or list them at the bottom without values (unvalued)"
+    )]
+    skip_draft: Option<DraftMode>,
+
+    #[clap(
+        long,
+        value_name = "KEY",
+        default_value = "draft",
+        help = "Boolean field marking a page as a draft for --skip-draft"
+    )]
+    draft_key: String,
+
+    #[clap(
+        long,
+        value_name = "REGEX",
+        help = "Take values of the first key from file names with the first capture group of a pattern (e.g. '^(\\d+)-')"
+    )]
+    from_filename: Option<String>,
+
+    #[clap(
+        long,
+        help = "Print the order without opening the interface or writing files"
+    )]
+    dry_run: bool,
+
+    #[clap(
+        long,
+        requires = "dry_run",
+        help = "With --dry-run, print the changes to the files that would be written as a unified diff instead of the order"
+    )]
+    diff: bool,
+
+    #[clap(
+        long,
+        conflicts_with = "dry_run",
+        help = "Print values of keys in files sorted by them and exit"
+    )]
+    print_key: bool,
+
+    #[clap(
+        long,
+        hide = true,
+        conflicts_with = "print_key",
+        help = "Permute values randomly among included pages and save them without opening the interface"
+    )]
+    shuffle: bool,
+
+    #[clap(
+        long,
+        value_name = "FIELD",
+        conflicts_with_all = ["print_key", "shuffle", "readonly"],
+        help = "Number pages by a date in their front matter, newest first, and save them without opening the interface (e.g. --from-date date)"
+    )]
+    from_date: Option<String>,
+
+    #[clap(
+        long,
+        help = "Print the changed files and their old and new values as JSON after quitting"
+    )]
+    json_summary: bool,
+
+    #[clap(
+        long,
+        help = "Print the changes as a patch on saving instead of writing files (apply it with patch -p0)"
+    )]
+    emit_script: bool,
+
+    #[clap(
+        long,
+        help = "Show values and titles in a single column for narrow terminals"
+    )]
+    compact: bool,
+
+    #[clap(
+        long,
+        help = "Put values in front of titles in the list (e.g. 012  Title)"
+    )]
+    number_titles: bool,
+
+    #[clap(
+        long,
+        value_name = "SYMBOL",
+        default_value = " >> ",
+        help = "Symbol in front of the selected page while it is picked"
+    )]
+    picked_symbol: String,
+
+    #[clap(
+        long,
+        value_name = "SYMBOL",
+        default_value = " >  ",
+        help = "Symbol in front of the selected page"
+    )]
+    unpicked_symbol: String,
+
+    #[clap(
+        long,
+        value_name = "NAME",
+        help = "Take settings not given on the command line from a profile in profiles.toml under the config directory (e.g. ~/.config/order_in_yaml_frontmatter/profiles.toml)"
+    )]
+    profile: Option<String>,
+
+    #[clap(long, help = "Restore the default layout of the interface")]
+    reset_view: bool,
+
+    #[clap(long, help = "Print settings in effect as TOML and exit")]
+    print_config: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse arguments following `--profile blog`, with the profile given as TOML.
+    /// `--profile blog`に続く引数を、TOMLで与えたプロファイルとともに解析する。
+    fn parse(arg_list: &[&str], profile: &str) -> Result<Cli> {
+        let full_arg_list = [env!("CARGO_PKG_NAME"), "--profile", "blog"]
+            .iter()
+            .chain(arg_list)
+            .map(OsString::from)
+            .collect();
+        Cli::parse_with_profile(full_arg_list, |name| {
+            Ok(Profile {
+                name: name.to_owned(),
+                setting_list: profile.parse()?,
+            })
+        })
+    }
+
+    #[test]
+    fn command_line_takes_precedence_over_profile() {
+        let arg = parse(
+            &["--limit", "5"],
+            "ext = [\"md\"]\nrecursive = true\nlimit = 3",
+        )
+        .unwrap();
+        assert_eq!(arg.extension, ["md"]);
+        assert!(arg.recursive);
+        assert_eq!(arg.limit, Some(5));
+    }
+
+    #[test]
+    fn environment_takes_precedence_over_profile() {
+        env::set_var("ORDER_YAML_KEY", "order");
+        let arg = parse(&[], "key = \"weight\"");
+        env::remove_var("ORDER_YAML_KEY");
+        assert_eq!(arg.unwrap().key, ["order"]);
+    }
+
+    #[test]
+    fn conflicting_flag_suppresses_profile_setting() {
+        let arg = parse(&["--no-live-resort"], "live-resort = true").unwrap();
+        assert!(!arg.live_resort);
+        assert!(arg.no_live_resort);
+    }
+
+    #[test]
+    fn unknown_option_in_profile_is_rejected() {
+        let err = parse(&[], "colour = true").err().unwrap();
+        assert_eq!(err.to_string(), "colour in profile blog is not an option");
+    }
+
+    #[test]
+    fn list_for_single_value_option_is_rejected() {
+        let err = parse(&[], "limit = [1, 2]").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "limit in profile blog takes a single value, not a list"
+        );
+    }
 }
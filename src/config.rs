@@ -0,0 +1,336 @@
+// Copyright (c) 2023 Yuichi Ishida
+//
+// Released under the MIT license.
+// see https://opensource.org/licenses/mit-license.php
+
+use crate::header_label::HeaderLabel;
+use crate::page::FrontMatterFormat;
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use serde_derive::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Settings in effect for a run.
+///
+/// 実行時に有効な設定。
+#[derive(Clone, Debug, Serialize)]
+pub struct Config {
+    pub key: Vec<String>,
+    pub key_candidates: Vec<String>,
+    pub key_type: KeyType,
+    pub target: PathBuf,
+    pub create_target: bool,
+    pub recursive: bool,
+    pub max_depth: Option<usize>,
+    pub traversal: Traversal,
+    pub dir_style: DirStyle,
+    pub group_by_dir: bool,
+    pub tier: Vec<Tier>,
+    pub select: Option<PathBuf>,
+    pub assume_yes: bool,
+    pub live_resort: bool,
+    pub wrap: bool,
+    pub display_desc: bool,
+    pub readonly: bool,
+    pub emit_script: bool,
+    pub value_as_string: bool,
+    pub keep_quoted: bool,
+    pub one_based: bool,
+    pub clean_excluded: bool,
+    pub normalize: bool,
+    pub unassigned_first: bool,
+    pub renumber_on_load: bool,
+    pub compact: bool,
+    pub number_titles: bool,
+    pub picked_symbol: String,
+    pub unpicked_symbol: String,
+    pub require_key: bool,
+    pub slug_order: bool,
+    pub extension: Vec<String>,
+    pub verbatim_body: Vec<String>,
+    pub format: FormatMode,
+    pub ext_format: Vec<ExtFormat>,
+    pub since: Option<String>,
+    pub limit: Option<usize>,
+    pub output_dir: Option<PathBuf>,
+    pub filter_tag: Option<String>,
+    pub skip_draft: Option<DraftMode>,
+    pub draft_key: String,
+    pub from_filename: Option<String>,
+    pub profile: Option<String>,
+    pub header_label: HeaderLabel,
+    pub view: ViewConfig,
+}
+
+/// How values of keys are read and written.
+///
+/// キーの値の読み書きの仕方。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyType {
+    /// Integers, renumbered from 0 on save.
+    #[default]
+    Integer,
+    /// Strings such as `1.2.0`, sorted naturally and exchanged between pages without renumbering.
+    String,
+}
+
+/// Order in which directories are walked with `--recursive`.
+///
+/// `--recursive`でディレクトリをたどる順序。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Traversal {
+    /// Descend into a subdirectory as soon as it is found.
+    #[default]
+    DepthFirst,
+    /// List all files in a directory before descending into its subdirectories.
+    BreadthFirst,
+}
+
+/// How directories of pages are shown in the TUI. Paths of files themselves are never changed.
+///
+/// TUIでのページのディレクトリの表示の仕方。ファイルのパス自体は変えない。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum DirStyle {
+    /// As the paths are found from the target directory.
+    #[default]
+    Full,
+    /// With the home directory abbreviated to `~`.
+    Home,
+    /// Relative to the target directory (`.` for the target directory itself).
+    Target,
+}
+
+/// How pages marked as drafts are handled with `--skip-draft`.
+///
+/// `--skip-draft`で下書きとされたページの扱い方。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum DraftMode {
+    /// Not listed at all.
+    #[default]
+    Exclude,
+    /// Listed at the bottom without values, and never numbered nor written.
+    Unvalued,
+}
+
+/// A named range of values beginning with a base value, e.g. `featured=0` and `normal=100`.
+/// A tier lasts until the base value of the next one.
+///
+/// 基準値から始まる、名前付きの値の範囲。`featured=0`や`normal=100`など。
+/// 範囲は次の階層の基準値の手前まで続く。
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Tier {
+    pub name: String,
+    pub base: i64,
+}
+
+impl FromStr for Tier {
+    type Err = anyhow::Error;
+
+    fn from_str(tier: &str) -> Result<Self> {
+        let (name, base) = tier
+            .split_once('=')
+            .with_context(|| format!("a tier is given as NAME=BASE: {}", tier))?;
+        Ok(Self {
+            name: name.to_owned(),
+            base: base
+                .parse()
+                .with_context(|| format!("failed to parse the base value of a tier: {}", tier))?,
+        })
+    }
+}
+
+/// Format in which FrontMatter of files is read, unless it is given to their extensions.
+///
+/// 拡張子に与えられていない場合に、ファイルのFrontMatterを読む形式。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum FormatMode {
+    /// YAML between `---` lines.
+    #[default]
+    Yaml,
+    /// TOML between `+++` lines.
+    Toml,
+    /// Either of them, as the opening line of each file shows.
+    Auto,
+}
+
+impl FormatMode {
+    /// The format in which FrontMatter is read, or None if it is taken from each file.
+    /// FrontMatterを読む形式。ファイルごとに決める場合はNone。
+    pub fn format(self) -> Option<FrontMatterFormat> {
+        match self {
+            Self::Yaml => Some(FrontMatterFormat::Yaml),
+            Self::Toml => Some(FrontMatterFormat::Toml),
+            Self::Auto => None,
+        }
+    }
+}
+
+/// A format of FrontMatter given to an extension, e.g. `md=yaml` and `toml-post=toml`.
+///
+/// 拡張子に与えたFrontMatterの形式。`md=yaml`や`toml-post=toml`など。
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ExtFormat {
+    pub extension: String,
+    pub format: FrontMatterFormat,
+}
+
+impl FromStr for ExtFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(ext_format: &str) -> Result<Self> {
+        let (extension, format) = ext_format
+            .split_once('=')
+            .with_context(|| format!("a format is given as EXT=FORMAT: {}", ext_format))?;
+        Ok(Self {
+            extension: extension.to_owned(),
+            format: format.parse()?,
+        })
+    }
+}
+
+/// Preferences of the TUI layout, kept across sessions.
+///
+/// セッションをまたいで保持されるTUIのレイアウトの設定。
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewConfig {
+    pub show_directory: bool,
+    pub show_legend: bool,
+    pub sort: Option<ViewSort>,
+}
+
+/// Column by which the list is sorted only for display, without changing values.
+///
+/// 値を変えずに、表示のためだけに一覧を並べる列。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ViewSort {
+    Title,
+    File,
+    Directory,
+}
+
+impl Default for ViewConfig {
+    fn default() -> Self {
+        Self {
+            show_directory: true,
+            show_legend: false,
+            sort: None,
+        }
+    }
+}
+
+impl ViewConfig {
+    const FILE_NAME: &'static str = "view.toml";
+
+    /// File holding the preferences, under the state (or config) directory of the user.
+    /// 設定を保持するファイル。ユーザーの状態（または設定）ディレクトリの下に置く。
+    fn path() -> Option<PathBuf> {
+        dirs::state_dir()
+            .or_else(dirs::config_dir)
+            .map(|dir| dir.join(env!("CARGO_PKG_NAME")).join(Self::FILE_NAME))
+    }
+
+    /// Read the preferences left by the last session, or the default ones if there are none.
+    /// 前回のセッションで残された設定を読む。なければ既定の設定にする。
+    pub fn load() -> Result<Self> {
+        match Self::path() {
+            Some(path) if path.is_file() => {
+                let content = fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read {}", path.display()))?;
+                toml::from_str(&content)
+                    .with_context(|| format!("failed to parse {}", path.display()))
+            }
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Write the preferences for the next session.
+    /// 次のセッションのために設定を書き込む。
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().context("failed to find a directory to keep view preferences")?;
+        fs::create_dir_all(path.parent().unwrap())
+            .with_context(|| format!("failed to create {}", path.parent().unwrap().display()))?;
+        fs::write(&path, toml::to_string(self)?)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+/// A named set of settings in the profile file, e.g. one for each site.
+/// Each setting is given by the long name of an option.
+///
+/// プロファイルのファイルにある名前付きの設定の組。例えばサイトごとに一つ用意する。
+/// 各設定はオプションの長い名前で与える。
+#[derive(Clone, Debug)]
+pub struct Profile {
+    pub name: String,
+    pub setting_list: toml::Table,
+}
+
+impl Profile {
+    const FILE_NAME: &'static str = "profiles.toml";
+
+    /// File holding profiles, under the config directory of the user.
+    /// プロファイルを保持するファイル。ユーザーの設定ディレクトリの下に置く。
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(env!("CARGO_PKG_NAME")).join(Self::FILE_NAME))
+    }
+
+    /// Read a profile of a given name.
+    /// 与えられた名前のプロファイルを読む。
+    pub fn load(name: &str) -> Result<Self> {
+        let path = Self::path().context("failed to find a directory holding profiles")?;
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let mut profile_list = content
+            .parse::<toml::Table>()
+            .with_context(|| format!("failed to parse {}", path.display()))?;
+        match profile_list.remove(name) {
+            Some(toml::Value::Table(setting_list)) => Ok(Self {
+                name: name.to_owned(),
+                setting_list,
+            }),
+            Some(_) => bail!("profile {} in {} is not a table", name, path.display()),
+            None => bail!("profile {} is not found in {}", name, path.display()),
+        }
+    }
+
+    /// Command line arguments giving a setting to an option of a long name, e.g. `--ext=md,mdx` for `ext = ["md", "mdx"]`.
+    /// A flag set to false gives nothing.
+    /// 長い名前のオプションに設定を与えるコマンドライン引数。例えば`ext = ["md", "mdx"]`は`--ext=md,mdx`になる。
+    /// falseにしたフラグは何も与えない。
+    pub fn arg_list(&self, long: &str, setting: &toml::Value) -> Result<Vec<String>> {
+        fn text(setting: &toml::Value) -> Option<String> {
+            match setting {
+                toml::Value::String(x) => Some(x.to_owned()),
+                toml::Value::Integer(x) => Some(x.to_string()),
+                _ => None,
+            }
+        }
+        let value = match setting {
+            toml::Value::Boolean(true) => return Ok(vec![format!("--{}", long)]),
+            toml::Value::Boolean(false) => return Ok(vec![]),
+            toml::Value::Array(setting_list) => setting_list
+                .iter()
+                .map(text)
+                .collect::<Option<Vec<_>>>()
+                .map(|text_list| text_list.join(",")),
+            setting => text(setting),
+        };
+        match value {
+            Some(value) => Ok(vec![format!("--{}={}", long, value)]),
+            None => bail!(
+                "{} in profile {} is not a string, an integer, a boolean or a list of them",
+                long,
+                self.name
+            ),
+        }
+    }
+}
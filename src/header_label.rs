@@ -0,0 +1,83 @@
+// Copyright (c) 2023 Yuichi Ishida
+//
+// Released under the MIT license.
+// see https://opensource.org/licenses/mit-license.php
+
+use anyhow::{bail, Context, Result};
+use serde_derive::Serialize;
+
+pub const TITLE: &str = "Title";
+pub const EXCLUDED: &str = "";
+pub const MODIFIED: &str = "";
+pub const NOTE: &str = "";
+pub const FILE: &str = "File";
+pub const DIRECTORY: &str = "Directory";
+pub const KEY: &str = "Key";
+pub const OLD: &str = "Old";
+pub const NEW: &str = "New";
+pub const PATH: &str = "Path";
+
+/// Labels of the table header, each of which can be renamed with `--header-label`.
+///
+/// 表のヘッダーのラベル。それぞれ`--header-label`で名前を変えられる。
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct HeaderLabel {
+    pub title: String,
+    pub file: String,
+    pub directory: String,
+}
+
+impl Default for HeaderLabel {
+    fn default() -> Self {
+        Self {
+            title: TITLE.to_owned(),
+            file: FILE.to_owned(),
+            directory: DIRECTORY.to_owned(),
+        }
+    }
+}
+
+impl HeaderLabel {
+    /// Default labels with some of them renamed by `NAME=LABEL`, e.g. `file=Path`.
+    /// 既定のラベルのうち、いくつかを`NAME=LABEL`で名前を変えたもの。例えば`file=Path`。
+    pub fn try_new(rename_list: &[String]) -> Result<Self> {
+        let mut header_label = Self::default();
+        for rename in rename_list {
+            let (name, label) = rename
+                .split_once('=')
+                .with_context(|| format!("a header label is given as NAME=LABEL: {}", rename))?;
+            let field = match name {
+                "title" => &mut header_label.title,
+                "file" => &mut header_label.file,
+                "directory" => &mut header_label.directory,
+                _ => bail!(
+                    "{} is not a header label (one of title, file and directory)",
+                    name
+                ),
+            };
+            *field = label.to_owned();
+        }
+        Ok(header_label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_new_renames_given_labels_only() {
+        let header_label =
+            HeaderLabel::try_new(&["file=Path".to_owned(), "directory=".to_owned()]).unwrap();
+        assert_eq!(
+            header_label,
+            HeaderLabel {
+                title: TITLE.to_owned(),
+                file: "Path".to_owned(),
+                directory: String::new(),
+            }
+        );
+        assert!(HeaderLabel::try_new(&["file".to_owned()]).is_err());
+        assert!(HeaderLabel::try_new(&["size=Size".to_owned()]).is_err());
+    }
+}
@@ -7,5 +7,18 @@ pub const UP: char = 'i';
 pub const DOWN: char = 'k';
 pub const PICK_TOGGLE: char = 'p';
 pub const INCLUDE_TOGGLE: char = 'x';
+pub const CYCLE_VARIABLE: char = 'v';
+pub const COPY_PATH: char = 'y';
+pub const SORT_BY_SLUG: char = 'g';
+pub const DIRECTORY_TOGGLE: char = 'c';
+pub const COMMAND: char = ':';
+pub const GRAB: char = 'b';
+pub const NOTE: char = 'n';
+pub const ARRANGE_TOGGLE: char = 'a';
+pub const MARK: char = 'm';
+pub const EDIT: char = 'e';
+pub const VIEW_SORT: char = 'o';
+pub const NEXT_TIER: char = 't';
+pub const LEGEND_TOGGLE: char = 'l';
 pub const QUIT: char = 'q';
 pub const SAVE: char = 's';
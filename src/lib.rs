@@ -3,7 +3,11 @@
 // Released under the MIT license.
 // see https://opensource.org/licenses/mit-license.php
 
-mod app;
+pub mod app;
 pub mod cli;
+pub mod config;
+pub mod header_label;
 mod key_bind;
-mod page;
+pub mod page;
+#[cfg(test)]
+mod test_util;
@@ -5,7 +5,8 @@
 
 use anyhow::Result;
 use order_in_yaml_frontmatter::cli::Cli;
+use std::process::ExitCode;
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
     Cli::run()
 }
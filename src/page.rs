@@ -3,24 +3,34 @@
 // Released under the MIT license.
 // see https://opensource.org/licenses/mit-license.php
 
+use crate::config::{Config, DraftMode, ExtFormat, FormatMode, KeyType, Tier, Traversal};
 use anyhow::{bail, Context, Result};
 use getset::{Getters, MutGetters, Setters};
-use std::cmp::Ordering;
+use regex::Regex;
+use serde_derive::Serialize;
+use serde_json::json;
+use std::borrow::Cow;
+use std::cmp::{self, Ordering};
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
 use std::fmt::Write as _;
 use std::fs;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::Write as _;
+use std::mem;
 use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::SystemTime;
 use tempfile::NamedTempFile;
-use yaml_rust::{Yaml, YamlEmitter};
+use yaml_rust::parser::{Event, Parser};
+use yaml_rust::yaml::Hash;
+use yaml_rust::{Yaml, YamlEmitter, YamlLoader};
 
 /// Maintains information about the file's Frontmatter.
-/// The value of value is changed first and then reflected in yaml by another function.
+/// The value of value is changed first, and FrontMatter is read again and updated only when the file is written.
 ///
 /// ファイルのFrontMatterに関する情報を保持する。
-/// valueの値が先に変更され、別の関数によりyamlに反映させる。
+/// valueの値が先に変更され、ファイルを書き込むときにだけFrontMatterを読み直して更新する。
 #[derive(Debug, Getters, MutGetters, Setters)]
 #[getset(get = "pub")]
 pub struct Page {
@@ -28,19 +38,146 @@ pub struct Page {
     #[getset(get_mut, set)]
     path: PathBuf,
 
-    /// FrontMatter
-    #[getset(get_mut, set)]
-    yaml: Yaml,
+    /// path of the file relative to the target directory
+    relative_path: PathBuf,
 
-    /// a new value of key in FrontMatter
-    #[getset(get_mut, set)]
-    value: Option<i64>,
+    /// new values of keys in FrontMatter
+    #[getset(skip)]
+    value_list: Vec<Option<i64>>,
+
+    /// old values of keys in FrontMatter
+    #[getset(skip)]
+    value_old_list: Vec<Option<i64>>,
+
+    /// values of keys right after loading, which differ from the old ones if they are renumbered on load
+    #[getset(skip)]
+    value_load_list: Vec<Option<i64>>,
+
+    /// values of keys read as they are when keys are strings
+    #[getset(skip)]
+    scalar_list: Vec<Option<Yaml>>,
+
+    /// values of keys as they were read when keys are strings
+    #[getset(skip)]
+    scalar_old_list: Vec<Option<Yaml>>,
+
+    /// whether each integer value is written as a quoted string in FrontMatter
+    #[getset(skip)]
+    quoted_list: Vec<bool>,
+
+    /// index of the key to reorder
+    #[getset(skip)]
+    key_idx: usize,
 
-    /// a old value of key in FrontMatter
-    value_old: Option<i64>,
+    /// whether each key applies to the page (its category matches the one given with the key)
+    #[getset(skip)]
+    in_scope_list: Vec<bool>,
+
+    /// whether each key is left without a value (null or commented out) in FrontMatter
+    #[getset(skip)]
+    leftover_list: Vec<bool>,
 
     /// title in FrontMatter
     title: Option<String>,
+
+    /// `slug` in FrontMatter, or the file stem lowercased with spaces replaced by hyphens
+    slug: String,
+
+    /// whether the file starts with a UTF-8 BOM
+    bom: bool,
+
+    /// blank lines before the closing line of FrontMatter
+    closing_prefix: String,
+
+    /// closing line of FrontMatter (`---` or `...`) with its line ending
+    closing_line: String,
+
+    /// line ending of FrontMatter (`\r\n` or `\n`), taken from its opening line
+    #[getset(skip)]
+    line_ending: &'static str,
+
+    /// whether another FrontMatter block follows right after the first one
+    extra_frontmatter: bool,
+
+    /// whether FrontMatter has anchors or aliases, which are kept by editing it line by line
+    anchored: bool,
+
+    /// whether the body is kept verbatim without looking into it, and only `---` closes FrontMatter
+    verbatim_body: bool,
+
+    /// format of FrontMatter
+    format: FrontMatterFormat,
+
+    /// end of the list to which the page is pinned by `pinned: first` or `pinned: last`
+    pin: Option<Pin>,
+
+    /// whether the file is reached through a symbolic link below the target directory
+    symlinked: bool,
+
+    /// whether the real path of the file is outside the target directory
+    outside_target: bool,
+
+    /// whether the page is a draft listed without values by `--skip-draft unvalued`
+    draft: bool,
+
+    /// note attached during a session, which is never written to the file
+    #[getset(set = "pub")]
+    note: Option<String>,
+}
+
+/// End of the list to which a page is pinned.
+///
+/// ページが固定されるリストの端。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pin {
+    First,
+    Last,
+}
+
+/// Pieces of a file split at the end of its FrontMatter.
+///
+/// FrontMatterの終わりで分割したファイルの各部分。
+struct FrontMatterBlock<'a> {
+    format: FrontMatterFormat,
+    prefix: &'a str,
+    opening_line: &'a str,
+    frontmatter: &'a str,
+    closing_prefix: &'a str,
+    closing_line: &'a str,
+    body: &'a str,
+}
+
+impl FrontMatterBlock<'_> {
+    /// Read FrontMatter as YAML, or None if it is empty YAML.
+    /// FrontMatterをYAMLとして読む。空のYAMLの場合はNone。
+    fn load(&self) -> Result<Option<Yaml>> {
+        match self.format {
+            FrontMatterFormat::Yaml => Ok(YamlLoader::load_from_str(self.frontmatter)?.pop()),
+            FrontMatterFormat::Toml => load_toml(self.frontmatter).map(Some),
+        }
+    }
+}
+
+/// Settings for writing values to FrontMatter.
+///
+/// FrontMatterに値を書き込むときの設定。
+#[derive(Clone, Copy)]
+struct WriteOption {
+    value_as_string: bool,
+    keep_quoted: bool,
+    one_based: bool,
+    clean_excluded: bool,
+    normalize: bool,
+}
+
+/// Settings for reading FrontMatter of a file, which depend on its extension.
+///
+/// ファイルのFrontMatterを読むときの設定。拡張子によって決まる。
+#[derive(Clone, Copy)]
+struct ReadOption {
+    verbatim_body: bool,
+    /// format of FrontMatter, or None to take the one its opening line shows
+    format: Option<FrontMatterFormat>,
 }
 
 /// List of files having FrontMatter.
@@ -58,9 +195,135 @@ pub struct Page {
 pub struct PageList {
     page_list: Vec<Page>,
 
-    /// variable name of FrontMatter
+    /// variable names of FrontMatter
+    #[getset(get = "pub")]
+    key_list: Vec<String>,
+
+    /// categories to which keys are limited
+    #[getset(get = "pub")]
+    category_list: Vec<Option<String>>,
+
+    /// index of the key to reorder
+    #[getset(get = "pub")]
+    key_idx: usize,
+
+    /// tag which pages must have in `tags` to be edited
+    #[getset(get = "pub")]
+    filter_tag: Option<String>,
+
+    /// how drafts are handled, and the field marking them
+    #[getset(skip)]
+    skip_draft: Option<(String, DraftMode)>,
+
+    /// Assign sequential numbers within each directory
     #[getset(get)]
-    key: String,
+    group_by_dir: bool,
+
+    /// tiers of values in ascending order of their base values
+    #[getset(get = "pub")]
+    tier_list: Vec<Tier>,
+
+    /// Write values as strings instead of integers
+    #[getset(get = "pub")]
+    value_as_string: bool,
+
+    /// Write values read as quoted strings back as strings
+    #[getset(get = "pub")]
+    keep_quoted: bool,
+
+    /// Write values starting from 1 and read them back as starting from 0
+    #[getset(get = "pub")]
+    one_based: bool,
+
+    /// extensions of files to handle
+    #[getset(get = "pub")]
+    extension_list: Vec<String>,
+
+    /// extensions of files whose bodies are kept verbatim
+    #[getset(get = "pub")]
+    verbatim_body_list: Vec<String>,
+
+    /// formats of FrontMatter given to extensions
+    #[getset(get = "pub")]
+    ext_format_list: Vec<ExtFormat>,
+
+    /// format of FrontMatter of files of the other extensions
+    #[getset(get = "pub")]
+    format: FormatMode,
+
+    /// files modified before this time are skipped
+    #[getset(get = "pub")]
+    since: Option<SystemTime>,
+
+    /// number of pages after which no more files are loaded
+    #[getset(get = "pub")]
+    limit: Option<usize>,
+
+    /// directory to which copies are written instead of the files themselves
+    #[getset(get = "pub")]
+    output_dir: Option<PathBuf>,
+
+    /// pattern to take values from file names
+    #[getset(get = "pub")]
+    from_filename: Option<Regex>,
+
+    /// Rewrite excluded pages which still have keys without values
+    #[getset(get = "pub")]
+    clean_excluded: bool,
+
+    /// Emit the whole FrontMatter of every page again on save
+    #[getset(get = "pub")]
+    normalize: bool,
+
+    /// Place pages without values before those with values
+    #[getset(get = "pub")]
+    unassigned_first: bool,
+
+    /// Renumber pages from 0 when they are sorted by their values, including on load
+    #[getset(get = "pub")]
+    renumber_on_load: bool,
+
+    /// type of values of keys
+    #[getset(get = "pub")]
+    key_type: KeyType,
+
+    /// order to walk directories
+    #[getset(get = "pub")]
+    traversal: Traversal,
+
+    /// directory whose files are listed
+    #[getset(get = "pub")]
+    target_dir: PathBuf,
+
+    /// Order pages without values by their slugs instead of the order they are found
+    #[getset(get = "pub")]
+    slug_order: bool,
+
+    /// Fail unless every file has FrontMatter with values of keys
+    #[getset(get = "pub")]
+    require_key: bool,
+
+    /// files without FrontMatter, reported when keys are required
+    missing_list: Vec<PathBuf>,
+
+    /// files skipped because they are not valid UTF-8
+    #[getset(get = "pub")]
+    non_utf8_list: Vec<PathBuf>,
+
+    /// reasons why files are skipped because a value of a key cannot be read
+    #[getset(get = "pub")]
+    unreadable_list: Vec<String>,
+}
+
+/// A changed value of a key of a page.
+///
+/// ページのキーの変更された値。
+#[derive(Debug)]
+pub struct PageChange<'a> {
+    pub path: &'a Path,
+    pub key: &'a str,
+    pub old_value: serde_json::Value,
+    pub new_value: serde_json::Value,
 }
 
 pub enum SwapDirection {
@@ -72,230 +335,3496 @@ pub enum SwapDirection {
 enum PageError {
     #[error("failed to get front matter: {0}")]
     NoFrontMatter(PathBuf),
-    #[error("failed to get an integer : {0}")]
-    NoIntegerKey(PathBuf),
+    #[error("{key}: expected integer, found {found} in {}", path.display())]
+    NoIntegerKey {
+        path: PathBuf,
+        key: String,
+        found: String,
+    },
+    #[error("{key}: expected scalar, found {found} in {}", path.display())]
+    NoScalarKey {
+        path: PathBuf,
+        key: String,
+        found: String,
+    },
+    #[error("{key}: expected {expected}, found {found} in {}", path.display())]
+    NonScalarKey {
+        path: PathBuf,
+        key: String,
+        found: &'static str,
+        expected: &'static str,
+    },
+    #[error("out of the categories of keys: {0}")]
+    OutOfCategory(PathBuf),
+    #[error("a draft: {0}")]
+    Draft(PathBuf),
+    #[error("not valid UTF-8: {0}")]
+    NonUtf8(PathBuf),
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
 
 impl Page {
-    fn try_new(path: &Path, key: &str) -> Result<Self, PageError> {
-        let yaml = frontmatter::parse(
-            &fs::read_to_string(&path).map_err(|err| PageError::Other(err.into()))?,
-        )
-        .map_err(|err| PageError::Other(err.into()))?
-        .ok_or_else(|| PageError::NoFrontMatter(path.to_owned()))?;
-        let value = match &yaml[key] {
-            Yaml::Integer(x) => Some(x.to_owned()),
-            Yaml::BadValue | Yaml::Null => Option::None,
-            _ => return Err(PageError::NoIntegerKey(path.to_owned())),
+    const BOM: char = '\u{feff}';
+
+    /// Fields holding categories of a page.
+    /// ページのカテゴリを持つフィールド。
+    const CATEGORY_FIELD_LIST: [&'static str; 3] = ["categories", "category", "tags"];
+
+    fn try_new(
+        path: &Path,
+        key_list: &[String],
+        category_list: &[Option<String>],
+        filter_tag: Option<&str>,
+        skip_draft: Option<(&str, DraftMode)>,
+        key_type: KeyType,
+        read_option: ReadOption,
+    ) -> Result<Self, PageError> {
+        let ReadOption {
+            verbatim_body,
+            format,
+        } = read_option;
+        let content = fs::read(path).map_err(|err| PageError::Other(err.into()))?;
+        let content =
+            String::from_utf8(content).map_err(|_| PageError::NonUtf8(path.to_owned()))?;
+        let (content, bom) = match content.strip_prefix(Self::BOM) {
+            Some(content) => (content, true),
+            None => (content.as_str(), false),
+        };
+        // FrontMatter in another format is not looked into, as if there were none.
+        // 他の形式のFrontMatterは、ないものとして中を見ない。
+        let block = split_frontmatter_block(content, verbatim_body)
+            .filter(|block| format.is_none_or(|format| block.format == format))
+            .ok_or_else(|| PageError::NoFrontMatter(path.to_owned()))?;
+        let format = block.format;
+        let yaml = block
+            .load()?
+            .ok_or_else(|| PageError::NoFrontMatter(path.to_owned()))?;
+        let page_category_list = Self::category_list(&yaml);
+        let mut in_scope_list = category_list
+            .iter()
+            .map(|category| {
+                category
+                    .as_ref()
+                    .is_none_or(|category| page_category_list.contains(category))
+            })
+            .collect::<Vec<_>>();
+        if !in_scope_list.contains(&true) {
+            return Err(PageError::OutOfCategory(path.to_owned()));
+        }
+        if let Some(tag) = filter_tag {
+            // A page without the tag is still listed, but no key applies to it.
+            // タグのないページも一覧には載せるが、どのキーも適用しない。
+            if !Self::string_list(&yaml["tags"])
+                .iter()
+                .any(|page_tag| page_tag == tag)
+            {
+                in_scope_list.fill(false);
+            }
+        }
+        let draft = match skip_draft {
+            Some((draft_key, draft_mode))
+                if Self::lookup(&yaml, draft_key).as_bool() == Some(true) =>
+            {
+                if draft_mode == DraftMode::Exclude {
+                    return Err(PageError::Draft(path.to_owned()));
+                }
+                // A draft is listed like a page without the tag of --filter-tag.
+                // 下書きは--filter-tagのタグのないページと同じように一覧に載せる。
+                in_scope_list.fill(false);
+                true
+            }
+            _ => false,
         };
-        let title = if let Yaml::String(x) = &yaml["title"] {
-            Some(x.to_owned())
+        let mut value_list = Vec::with_capacity(key_list.len());
+        let mut scalar_list = Vec::with_capacity(key_list.len());
+        let mut quoted_list = Vec::with_capacity(key_list.len());
+        let mut leftover_list = Vec::with_capacity(key_list.len());
+        for (key, in_scope) in key_list.iter().zip(&in_scope_list) {
+            quoted_list.push(matches!(Self::lookup(&yaml, key), Yaml::String(_)));
+            if !in_scope {
+                value_list.push(None);
+                scalar_list.push(None);
+                leftover_list.push(false);
+                continue;
+            }
+            let last_segment = key.rsplit('.').next().unwrap();
+            leftover_list.push(
+                matches!(Self::lookup(&yaml, key), Yaml::Null)
+                    || block
+                        .frontmatter
+                        .lines()
+                        .any(|line| is_commented_key(line, last_segment, format)),
+            );
+            if let found @ ("Array" | "Hash") = yaml_type_name(Self::lookup(&yaml, key)) {
+                return Err(PageError::NonScalarKey {
+                    path: path.to_owned(),
+                    key: key.to_owned(),
+                    found,
+                    expected: match key_type {
+                        KeyType::Integer => "integer",
+                        KeyType::String => "scalar",
+                    },
+                });
+            }
+            if key_type == KeyType::String {
+                // Values are ranked later, so any number stands in for now.
+                // 値は後で順位付けするため、ここでは仮の数を入れておく。
+                let scalar = match Self::lookup(&yaml, key) {
+                    scalar @ (Yaml::String(_) | Yaml::Integer(_) | Yaml::Real(_)) => {
+                        Some(scalar.to_owned())
+                    }
+                    Yaml::BadValue | Yaml::Null => None,
+                    found => {
+                        return Err(PageError::NoScalarKey {
+                            path: path.to_owned(),
+                            key: key.to_owned(),
+                            found: found_text(found),
+                        })
+                    }
+                };
+                value_list.push(scalar.as_ref().map(|_| 0));
+                scalar_list.push(scalar);
+                continue;
+            }
+            scalar_list.push(None);
+            let no_integer_key = |found: &Yaml| PageError::NoIntegerKey {
+                path: path.to_owned(),
+                key: key.to_owned(),
+                found: found_text(found),
+            };
+            value_list.push(match Self::lookup(&yaml, key) {
+                Yaml::Integer(x) => Some(x.to_owned()),
+                found @ Yaml::String(x) => Some(x.parse().map_err(|_| no_integer_key(found))?),
+                Yaml::BadValue | Yaml::Null => Option::None,
+                found => return Err(no_integer_key(found)),
+            });
+        }
+        let title = yaml_to_display_string(&yaml["title"]);
+        let slug = if let Yaml::String(x) = &yaml["slug"] {
+            x.to_owned()
         } else {
-            None
+            slugify(&path.file_stem().unwrap_or_default().to_string_lossy())
+        };
+        let pin = match yaml["pinned"].as_str() {
+            Some("first") => Some(Pin::First),
+            Some("last") => Some(Pin::Last),
+            _ => None,
         };
         Ok(Self {
             path: path.to_owned(),
-            yaml,
-            value,
-            value_old: value,
+            relative_path: path.to_owned(),
+            value_old_list: value_list.clone(),
+            value_load_list: value_list.clone(),
+            value_list,
+            scalar_old_list: scalar_list.clone(),
+            scalar_list,
+            quoted_list,
+            key_idx: 0,
+            in_scope_list,
+            leftover_list,
             title,
+            slug,
+            bom,
+            closing_prefix: block.closing_prefix.to_owned(),
+            closing_line: block.closing_line.to_owned(),
+            line_ending: if block.opening_line.ends_with("\r\n") {
+                "\r\n"
+            } else {
+                "\n"
+            },
+            extra_frontmatter: !verbatim_body && Self::has_extra_frontmatter(block.body),
+            anchored: format == FrontMatterFormat::Yaml && has_anchor(block.frontmatter),
+            verbatim_body,
+            format,
+            pin,
+            symlinked: false,
+            outside_target: false,
+            draft,
+            note: None,
         })
     }
-    fn substitute_value(&mut self, key: &str) {
-        match &mut self.yaml {
+    /// Whether the body opens with another block that looks like FrontMatter (a mapping between `---` or `+++` lines).
+    /// 本文が別のFrontMatterらしきブロック（`---`または`+++`の行で挟まれたマッピング）で始まるかどうか。
+    fn has_extra_frontmatter(body: &str) -> bool {
+        split_frontmatter_block(body.trim_start_matches(['\r', '\n']), false)
+            .is_some_and(|block| matches!(block.load(), Ok(Some(Yaml::Hash(_)))))
+    }
+    /// Categories of a page, given as a list or a string.
+    /// ページのカテゴリ。リストまたは文字列で与えられる。
+    fn category_list(yaml: &Yaml) -> Vec<String> {
+        Self::CATEGORY_FIELD_LIST
+            .iter()
+            .flat_map(|field| Self::string_list(&yaml[*field]))
+            .collect()
+    }
+    /// Strings of a field given as a list or a string.
+    /// リストまたは文字列で与えられたフィールドの文字列。
+    fn string_list(yaml: &Yaml) -> Vec<String> {
+        match yaml {
+            Yaml::String(string) => vec![string.to_owned()],
+            Yaml::Array(array) => array
+                .iter()
+                .filter_map(|string| string.as_str().map(str::to_owned))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+    /// Whether the key to reorder applies to the page.
+    /// 並べ替えの対象のキーがページに適用されるかどうか。
+    pub fn is_in_scope(&self) -> bool {
+        self.in_scope_list[self.key_idx]
+    }
+    /// A new value of the key to reorder.
+    /// 並べ替えの対象のキーの新しい値。
+    pub fn value(&self) -> &Option<i64> {
+        &self.value_list[self.key_idx]
+    }
+    pub fn set_value(&mut self, value: Option<i64>) -> &mut Self {
+        self.value_list[self.key_idx] = value;
+        self
+    }
+    /// The value of the key to reorder as written in FrontMatter, when keys are strings.
+    /// キーが文字列の場合の、FrontMatterに書かれている通りの並べ替えの対象のキーの値。
+    fn scalar_text(&self) -> Option<String> {
+        self.scalar_list[self.key_idx].as_ref().map(scalar_text)
+    }
+    /// The value of the key to reorder as text: the string when keys are strings, otherwise the integer.
+    /// 並べ替えの対象のキーの値の文字列。キーが文字列の場合はその文字列、そうでなければ整数。
+    pub fn value_text(&self) -> Option<String> {
+        self.value()
+            .map(|value| self.scalar_text().unwrap_or_else(|| value.to_string()))
+    }
+    /// Read values written from 1 as values from 0, as the ones in the session.
+    /// A value below 1 is read below 0, so that it stays apart from 1 (see `below_one_summary`).
+    /// 1から書かれた値を、セッションでの値と同じく0からの値として読む。
+    /// 1未満の値は0未満として読み、1と区別したままにする（`below_one_summary`を参照）。
+    fn shift_from_one_based(&mut self) {
+        for value_list in [
+            &mut self.value_list,
+            &mut self.value_old_list,
+            &mut self.value_load_list,
+        ] {
+            for value in value_list.iter_mut().flatten() {
+                *value = value.saturating_sub(1);
+            }
+        }
+    }
+    /// Take the value of the key to reorder from the file name.
+    /// The first capture group (or the whole match) of a pattern is used.
+    /// 並べ替えの対象のキーの値をファイル名から取る。
+    /// パターンの最初のキャプチャグループ（なければマッチ全体）を使う。
+    fn seed_value_from_filename(&mut self, regex: &Regex) -> Result<()> {
+        if !self.is_in_scope() {
+            return Ok(());
+        }
+        let file_name = self.path.file_name().unwrap().to_string_lossy();
+        if let Some(captures) = regex.captures(&file_name) {
+            let matched = captures.get(1).or_else(|| captures.get(0)).unwrap();
+            let value = matched.as_str().parse().with_context(|| {
+                format!(
+                    "failed to get an integer from the file name: {}",
+                    self.path.display()
+                )
+            })?;
+            self.set_value(Some(value));
+        }
+        Ok(())
+    }
+    /// Directory and file name for display, split by `split_path_text` with the separators of the platform.
+    /// `split_path_text`でプラットフォームの区切り文字により分割した、表示用のディレクトリとファイル名。
+    fn split_path(&self) -> (Cow<'_, str>, Cow<'_, str>) {
+        match self.path.to_string_lossy() {
+            Cow::Borrowed(path) => {
+                let (dir, file_name) = split_path_text(path, SEPARATOR_LIST);
+                (Cow::from(dir), Cow::from(file_name))
+            }
+            Cow::Owned(path) => {
+                let (dir, file_name) = split_path_text(&path, SEPARATOR_LIST);
+                (Cow::from(dir.to_owned()), Cow::from(file_name.to_owned()))
+            }
+        }
+    }
+    /// File name for display, with characters not in UTF-8 replaced.
+    /// 表示用のファイル名。UTF-8でない文字は置き換える。
+    pub fn file_name(&self) -> Cow<'_, str> {
+        self.split_path().1
+    }
+    /// Directory for display, with separators of the platform (e.g. `\\` on Windows).
+    /// 表示用のディレクトリ。区切り文字はプラットフォームのもの（Windowsでは`\\`など）。
+    pub fn dir_name(&self) -> Cow<'_, str> {
+        self.split_path().0
+    }
+    /// The value of the key to reorder as it is in the file.
+    /// ファイル上の並べ替えの対象のキーの値。
+    pub fn value_old(&self) -> &Option<i64> {
+        &self.value_old_list[self.key_idx]
+    }
+    /// A value of a key as JSON: the string when keys are strings, otherwise the integer.
+    /// JSONとしてのキーの値。キーが文字列の場合は文字列、そうでなければ整数。
+    fn json_value(value: Option<i64>, scalar: Option<&Yaml>) -> serde_json::Value {
+        match (value, scalar) {
+            (None, _) => serde_json::Value::Null,
+            (Some(_), Some(scalar)) => json!(scalar_text(scalar)),
+            (Some(value), None) => json!(value),
+        }
+    }
+    /// Whether a value of any key differs from the old one.
+    /// いずれかのキーの値が古い値と異なるかどうか。
+    pub fn is_modified(&self) -> bool {
+        self.value_list != self.value_old_list
+    }
+    /// Whether a value of any key has been changed since loading, that is, by the user.
+    /// 読み込んだ後に、つまりユーザーによって、いずれかのキーの値が変えられたかどうか。
+    pub fn is_edited(&self) -> bool {
+        self.value_list != self.value_load_list
+    }
+    /// Follow a dot-separated key such as `menu.main.weight`.
+    /// `menu.main.weight`のようにドットで区切られたキーをたどる。
+    fn lookup<'a>(yaml: &'a Yaml, key: &str) -> &'a Yaml {
+        key.split('.').fold(yaml, |yaml, segment| &yaml[segment])
+    }
+    /// Reflect the values in FrontMatter read again from the file.
+    /// With `--one-based`, 1 is added to each value.
+    /// ファイルから読み直したFrontMatterに値を反映させる。
+    /// `--one-based`では各値に1を足す。
+    fn substitute_value(
+        &self,
+        yaml: &mut Yaml,
+        key_list: &[String],
+        value_as_string: bool,
+        keep_quoted: bool,
+        one_based: bool,
+    ) -> Result<()> {
+        for ((((key, value), scalar), in_scope), quoted) in key_list
+            .iter()
+            .zip(self.value_list.clone())
+            .zip(self.scalar_list.clone())
+            .zip(self.in_scope_list.clone())
+            .zip(self.quoted_list.clone())
+        {
+            if !in_scope {
+                continue;
+            }
+            let value = match (scalar, value) {
+                (Some(scalar), _) => Some(scalar),
+                (None, Some(value)) => {
+                    let value = if one_based {
+                        value.checked_add(1).with_context(|| {
+                            format!(
+                                "{}: {} is too large to be written from 1 in {}",
+                                key,
+                                value,
+                                self.path.display()
+                            )
+                        })?
+                    } else {
+                        value
+                    };
+                    if value_as_string || (keep_quoted && quoted) {
+                        Some(Yaml::String(value.to_string()))
+                    } else {
+                        Some(Yaml::Integer(value))
+                    }
+                }
+                (None, None) => None,
+            };
+            self.substitute_key(yaml, key, value)?;
+        }
+        Ok(())
+    }
+    fn substitute_key(&self, mut yaml: &mut Yaml, key: &str, value: Option<Yaml>) -> Result<()> {
+        let mut segment_list = key.split('.').collect::<Vec<_>>();
+        let last_segment = segment_list.pop().unwrap();
+        for segment in segment_list {
+            match yaml {
+                Yaml::Hash(hash) => {
+                    let segment = Yaml::String(segment.to_owned());
+                    if value.is_none() && !hash.contains_key(&segment) {
+                        return Ok(());
+                    }
+                    yaml = hash
+                        .entry(segment)
+                        .or_insert_with(|| Yaml::Hash(Hash::new()));
+                }
+                _ => bail!("failed to set {} in {}", key, self.path.display()),
+            }
+        }
+        match yaml {
             Yaml::Hash(hash) => {
-                if let Some(value) = self.value {
-                    hash.insert(Yaml::String(key.to_owned()), Yaml::Integer(value));
+                let last_segment = Yaml::String(last_segment.to_owned());
+                if let Some(value) = value {
+                    // Update in place so that the order of sibling keys is kept.
+                    // 兄弟のキーの順序を保つため、その場で更新する。
+                    if let Some(current_value) = hash.get_mut(&last_segment) {
+                        *current_value = value;
+                    } else {
+                        hash.insert(last_segment, value);
+                    }
                 } else {
-                    hash.remove(&Yaml::String(key.to_owned()));
+                    hash.remove(&last_segment);
                 }
             }
-            _ => panic!(),
+            _ => bail!("failed to set {} in {}", key, self.path.display()),
         }
+        Ok(())
+    }
+    /// Whether an excluded page still has a key left in FrontMatter.
+    /// 除外されたページのFrontMatterにキーが残っているかどうか。
+    fn has_leftover_key(&self) -> bool {
+        self.value_list
+            .iter()
+            .zip(&self.leftover_list)
+            .any(|(value, leftover)| value.is_none() && *leftover)
+    }
+    /// Read a field of FrontMatter again from the file, e.g. a field not kept in the page such as `date`.
+    /// Dots separate nested keys as in keys to reorder.
+    /// FrontMatterのフィールドをファイルから読み直す。例えば`date`のようにページに保持していないフィールド。
+    /// 並べ替えのキーと同じく、ドットで入れ子のキーを区切る。
+    fn read_field(&self, field: &str) -> Result<Yaml> {
+        let content = fs::read_to_string(&self.path)
+            .with_context(|| format!("failed to read {}", self.path.display()))?;
+        let block = split_frontmatter_block(
+            content.strip_prefix(Self::BOM).unwrap_or(&content),
+            self.verbatim_body,
+        )
+        .with_context(|| format!("failed to get front matter: {}", self.path.display()))?;
+        let frontmatter = block
+            .load()?
+            .with_context(|| format!("failed to get front matter: {}", self.path.display()))?;
+        Ok(Self::lookup(&frontmatter, field).to_owned())
+    }
+    /// Whether saving rewrites the file: a value has changed, a leftover key is cleaned, or FrontMatter is normalized.
+    /// 保存でファイルを書き換えるかどうか。値が変わった、残ったキーを取り除く、またはFrontMatterを正規化する場合。
+    fn is_rewritten(&self, clean_excluded: bool, normalize: bool) -> bool {
+        normalize || self.is_modified() || (clean_excluded && self.has_leftover_key())
     }
-    fn overwrite_frontmatter(&mut self) -> Result<()> {
-        if self.value != self.value_old {
+    /// The content of the file before and after the values are reflected, or None if it is unchanged.
+    /// Only the lines of changed keys are edited, keeping comments and the style of the other lines,
+    /// and the whole FrontMatter is emitted again only if that is not possible or with `normalize`.
+    /// 値を反映する前と後のファイルの内容。変更がなければNone。
+    /// 変わったキーの行だけを書き換え、コメントや他の行の書き方を保つ。
+    /// それができない場合と`normalize`の場合だけ、FrontMatter全体を出力し直す。
+    fn new_content(
+        &self,
+        key_list: &[String],
+        write_option: &WriteOption,
+    ) -> Result<Option<(String, String)>> {
+        let WriteOption {
+            value_as_string,
+            keep_quoted,
+            one_based,
+            clean_excluded,
+            normalize,
+        } = *write_option;
+        if self.is_rewritten(clean_excluded, normalize) {
             let mut new_file_content = String::new();
-            let mut emitter = YamlEmitter::new(&mut new_file_content);
-            emitter.dump(&self.yaml)?;
-            writeln!(new_file_content, "\n---")?;
-            let tempfile = NamedTempFile::new()?;
-            let buf_reader = BufReader::new(File::open(&self.path)?);
-            let mut end_yaml = false;
-            for line_result in buf_reader.lines().skip(1) {
-                let line = line_result?;
-                if end_yaml {
-                    writeln!(new_file_content, "{}", line)?;
-                } else if line == "---" {
-                    end_yaml = true
+            if self.bom {
+                new_file_content.push(Self::BOM);
+            }
+            let content = fs::read_to_string(&self.path)?;
+            let block = split_frontmatter_block(
+                content.strip_prefix(Self::BOM).unwrap_or(&content),
+                self.verbatim_body,
+            )
+            .filter(|block| block.format == self.format)
+            .with_context(|| format!("failed to get front matter: {}", self.path.display()))?;
+            new_file_content.push_str(block.prefix);
+            let mut frontmatter = block
+                .load()?
+                .with_context(|| format!("failed to get front matter: {}", self.path.display()))?;
+            self.substitute_value(
+                &mut frontmatter,
+                key_list,
+                value_as_string,
+                keep_quoted,
+                one_based,
+            )?;
+            // TOML is always edited line by line, as it has no emitter here.
+            // TOMLはここに出力するものがないため、常に行ごとに書き換える。
+            let toml = self.format == FrontMatterFormat::Toml;
+            let mut edited_frontmatter = (!normalize || toml).then(|| block.frontmatter.to_owned());
+            for (key_idx, key) in key_list.iter().enumerate() {
+                let Some(frontmatter_text) = &edited_frontmatter else {
+                    break;
+                };
+                let value_list = (&self.value_list[key_idx], &self.value_old_list[key_idx]);
+                let cleaned = clean_excluded && self.leftover_list[key_idx];
+                if !self.in_scope_list[key_idx] || (value_list.0 == value_list.1 && !cleaned) {
+                    continue;
+                }
+                let value = match Self::lookup(&frontmatter, key) {
+                    Yaml::BadValue => None,
+                    value => Some(value),
+                };
+                edited_frontmatter = match self.format {
+                    FrontMatterFormat::Yaml => edit_yaml_line(frontmatter_text, key, value),
+                    FrontMatterFormat::Toml => edit_toml_line(frontmatter_text, key, value),
+                };
+                if cleaned {
+                    edited_frontmatter = edited_frontmatter
+                        .map(|text| remove_commented_key(&text, key, self.format));
+                }
+                // The emitter expands aliases and drops anchors, so a file with them is never emitted again.
+                // エミッタはエイリアスを展開しアンカーを落とすため、それらを持つファイルは出力し直さない。
+                if edited_frontmatter.is_none() && self.anchored {
+                    bail!(
+                        "failed to set {} in {} without breaking its anchors",
+                        key,
+                        self.path.display()
+                    );
+                }
+                if edited_frontmatter.is_none() && toml {
+                    bail!(
+                        "failed to set {} in {} by editing its line",
+                        key,
+                        self.path.display()
+                    );
+                }
+            }
+            // A line edited by mistake (e.g. one in a multi-line string) shows up as another change in TOML.
+            // 誤って書き換えた行（複数行の文字列の中の行など）は、TOMLの別の変更として現れる。
+            if let Some(edited_toml) = edited_frontmatter.as_deref().filter(|_| toml) {
+                if load_toml(edited_toml).ok().map(sort_key) != Some(sort_key(frontmatter.clone()))
+                {
+                    bail!(
+                        "failed to set {} in {} by editing its lines",
+                        key_list.join(", "),
+                        self.path.display()
+                    );
+                }
+            }
+            if let Some(frontmatter_text) = edited_frontmatter {
+                new_file_content.push_str(block.opening_line);
+                new_file_content.push_str(&frontmatter_text);
+                new_file_content.push_str(block.closing_line);
+            } else {
+                let mut yaml = String::new();
+                let mut emitter = YamlEmitter::new(&mut yaml);
+                emitter.dump(&frontmatter)?;
+                // The emitter opens with a `---` line of its own, which is replaced by the one in the file
+                // (e.g. with trailing spaces or CRLF) so that the opening line is kept as it is.
+                // エミッタは独自の`---`の行で始めるため、ファイルの開始行（末尾の空白やCRLFなど）に置き換えて保つ。
+                // The emitter breaks lines with LF, which is changed to the line ending of the file.
+                // エミッタはLFで改行するため、ファイルの改行に置き換える。
+                match yaml.strip_prefix("---\n") {
+                    Some(yaml) => {
+                        new_file_content.push_str(block.opening_line);
+                        new_file_content.push_str(&yaml.replace('\n', self.line_ending));
+                    }
+                    None => new_file_content.push_str(&yaml.replace('\n', self.line_ending)),
                 }
+                write!(
+                    new_file_content,
+                    "{}{}{}",
+                    self.line_ending, self.closing_prefix, self.closing_line
+                )?;
             }
-            fs::write(&tempfile, new_file_content)?;
-            fs::copy(tempfile, &self.path)?;
+            new_file_content.push_str(block.body);
+            if new_file_content == content {
+                return Ok(None);
+            }
+            Ok(Some((content.clone(), new_file_content)))
+        } else {
+            Ok(None)
         }
-        Ok(())
     }
-}
-
-impl Deref for PageList {
-    type Target = Vec<Page>;
-    fn deref(&self) -> &Self::Target {
-        &self.page_list
+    /// Write a copy of the file to its relative path under the output directory, whether or not its values have changed.
+    /// 出力ディレクトリの下の相対パスにファイルのコピーを書き込む。値が変わったかどうかによらない。
+    fn write_copy(
+        &self,
+        key_list: &[String],
+        write_option: &WriteOption,
+        output_dir: &Path,
+    ) -> Result<()> {
+        let new_file_content = match self.new_content(key_list, write_option)? {
+            Some((_, new_file_content)) => new_file_content.into_bytes(),
+            None => fs::read(&self.path)?,
+        };
+        let output_path = output_dir.join(&self.relative_path);
+        if let Some(dir) = output_path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        fs::write(&output_path, new_file_content)
+            .with_context(|| format!("failed to write {}", output_path.display()))?;
+        Ok(())
     }
-}
-impl DerefMut for PageList {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.page_list
+    fn overwrite_frontmatter(&self, key_list: &[String], write_option: &WriteOption) -> Result<()> {
+        if let Some((_, new_file_content)) = self.new_content(key_list, write_option)? {
+            // The temporary file is made next to the original so that it replaces the original
+            // by a rename, which works across platforms (including Windows, where the original is overwritten).
+            // 一時ファイルを元のファイルと同じ場所に作り、名前の変更で置き換える。
+            // これはWindowsを含めどのプラットフォームでも動く（Windowsでは元のファイルが上書きされる）。
+            let dir = match self.path.parent() {
+                Some(dir) if dir != Path::new("") => dir,
+                _ => Path::new("."),
+            };
+            let mut tempfile = NamedTempFile::new_in(dir)?;
+            tempfile.write_all(new_file_content.as_bytes())?;
+            fs::set_permissions(tempfile.path(), fs::metadata(&self.path)?.permissions())?;
+            tempfile
+                .persist(&self.path)
+                .with_context(|| format!("failed to overwrite {}", self.path.display()))?;
+        }
+        Ok(())
     }
 }
 
-impl PageList {
-    pub fn try_new(key: &str, target_dir: &Path, recursive: bool) -> Result<Self> {
-        let page_list = Self {
-            page_list: Vec::new(),
-            key: key.to_owned(),
-        };
-        let mut page_list = page_list.append_page_list(target_dir, recursive)?;
-        page_list.sort_and_fix();
-        Ok(page_list)
-    }
-
-    /// Add page lists
-    fn append_page_list(self, target_dir: &Path, recursive: bool) -> Result<Self> {
-        let mut page_list = self;
-        for entry_result in target_dir
-            .read_dir()
-            .with_context(|| format!("faild to open {}", target_dir.display()))?
-        {
-            let path = entry_result?.path();
-            if path.is_file()
-                && (path.extension() == Some(OsStr::new("html"))
-                    || path.extension() == Some(OsStr::new("md")))
+/// Whether YAML has anchors (`&name`) or aliases (`*name`).
+/// YAMLがアンカー（`&name`）またはエイリアス（`*name`）を持つかどうか。
+fn has_anchor(yaml: &str) -> bool {
+    let mut parser = Parser::new(yaml.chars());
+    while let Ok((event, _)) = parser.next() {
+        match event {
+            Event::Alias(_) => return true,
+            Event::Scalar(_, _, anchor_id, _)
+            | Event::SequenceStart(anchor_id)
+            | Event::MappingStart(anchor_id)
+                if anchor_id > 0 =>
             {
-                match Page::try_new(&path, &page_list.key) {
-                    Ok(page) => page_list.push(page),
-                    Err(PageError::NoFrontMatter(_)) => continue,
-                    Err(err) => return Err(err.into()),
-                }
-            } else if recursive && path.is_dir() {
-                page_list = page_list.append_page_list(&path, recursive)?;
+                return true
             }
+            Event::StreamEnd => break,
+            _ => (),
         }
-        Ok(page_list)
     }
+    false
+}
 
-    /// Sort and assign sequential numbers beginning with 0. None is greater than Some.
-    /// ソートして0始まりの連番を割り当てる。NoneはSomeと比較すると大きい。
-    fn sort_and_fix(&mut self) {
-        self.sort_by(|a, b| {
-            if let Some(a_value) = a.value() {
-                if let Some(b_value) = b.value() {
-                    a_value.cmp(b_value)
-                } else {
-                    Ordering::Less
-                }
-            } else if b.value().is_some() {
-                Ordering::Greater
-            } else {
-                Ordering::Equal
+/// Set a dot-separated key in YAML text by editing only its line, so that anchors, aliases, and comments elsewhere are kept.
+/// An anchor and a trailing comment on the line itself are kept too, and a quoted key (e.g. `"weight": 5`) is matched as well.
+/// Returns None if the key cannot be edited this way (e.g. its parent is missing or its value spans several lines).
+/// YAMLのテキストでドット区切りのキーをその行だけ書き換えて設定し、他の場所のアンカー、エイリアス、コメントを保つ。
+/// その行自体のアンカーと末尾のコメントも保ち、引用符で囲まれたキー（`"weight": 5`など）にも一致させる。
+/// この方法で書き換えられない場合（親がない、値が複数行にわたるなど）はNoneを返す。
+fn edit_yaml_line(yaml: &str, key: &str, value: Option<&Yaml>) -> Option<String> {
+    fn indent_of(line: &str) -> Option<usize> {
+        let trimmed_line = line.trim_start_matches(' ');
+        if trimmed_line.trim().is_empty() || trimmed_line.starts_with('#') {
+            None
+        } else {
+            Some(line.len() - trimmed_line.len())
+        }
+    }
+    let line_ending = if yaml.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut line_list = yaml
+        .split_inclusive('\n')
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    let mut range = 0..line_list.len();
+    let mut child_indent = 0;
+    let segment_list = key.split('.').collect::<Vec<_>>();
+    for (segment_idx, segment) in segment_list.iter().enumerate() {
+        child_indent = range
+            .clone()
+            .find_map(|idx| indent_of(&line_list[idx]))
+            .unwrap_or(child_indent);
+        let found = range.clone().find_map(|idx| {
+            if indent_of(&line_list[idx]) != Some(child_indent) {
+                return None;
             }
+            let key_len = key_text_len(&line_list[idx][child_indent..], segment)?;
+            Some((idx, key_len))
         });
-        let mut current_value = 0;
-        for page in self.iter_mut() {
-            if page.value().is_some() {
-                page.set_value(Some(current_value));
-                current_value += 1;
+        let Some((found_idx, key_len)) = found else {
+            // A key written in another way (e.g. quoted with escapes) may be there, and adding it again would duplicate it.
+            // 別の書き方（エスケープを含む引用符など）のキーがあるかもしれず、追加すると重複してしまう。
+            if range.clone().any(|idx| {
+                indent_of(&line_list[idx]) == Some(child_indent)
+                    && line_list[idx][child_indent..].starts_with(['"', '\'', '?', '{'])
+            }) {
+                return None;
             }
-        }
-    }
-
-    /// Remove the value if it exists, otherwise assign it.
-    /// valueに値があれば外し、そうでなければ代入する
-    pub fn toggle_value(&mut self, idx: usize) -> Result<()> {
-        let mut pre_value = 0;
-        let unset;
-        if let Some(page) = self.get_mut(idx) {
-            if page.value().is_some() {
-                unset = true;
-                page.set_value(None);
-            } else {
-                unset = false;
-                for pre_page in self.iter().take(idx) {
-                    if let Some(x) = pre_page.value() {
-                        pre_value = *x;
-                    }
-                }
+            // Add the rest of the key after the last line of its parent.
+            // キーの残りを親の最後の行の後に追加する。
+            // A missing key has nothing to remove.
+            // ないキーには取り除くものがない。
+            let Some(value) = value else {
+                return Some(line_list.concat());
+            };
+            let mut new_line_list = Vec::new();
+            for (depth, segment) in segment_list[segment_idx..].iter().enumerate() {
+                new_line_list.push(format!(
+                    "{}{}:",
+                    " ".repeat(child_indent + 2 * depth),
+                    segment
+                ));
+            }
+            new_line_list.last_mut().unwrap().push(' ');
+            new_line_list
+                .last_mut()
+                .unwrap()
+                .push_str(&emit_scalar(value)?);
+            let insert_idx = range
+                .clone()
+                .rev()
+                .find(|&idx| indent_of(&line_list[idx]).is_some())
+                .map_or(range.start, |idx| idx + 1);
+            if insert_idx > 0 && !line_list[insert_idx - 1].ends_with('\n') {
+                line_list[insert_idx - 1].push_str(line_ending);
+            }
+            for (offset, new_line) in new_line_list.into_iter().enumerate() {
+                line_list.insert(insert_idx + offset, new_line + line_ending);
+            }
+            return Some(line_list.concat());
+        };
+        let key_text = line_list[found_idx][child_indent..child_indent + key_len].to_owned();
+        let rest = line_list[found_idx][child_indent + key_len + 1..].trim();
+        let (anchor, rest) = match rest.strip_prefix('&') {
+            Some(rest) => {
+                let (anchor, rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                (Some(anchor), rest.trim_start())
+            }
+            None => (None, rest),
+        };
+        let value_follows = rest.is_empty() || rest.starts_with('#');
+        if segment_idx < segment_list.len() - 1 {
+            if !value_follows {
+                return None;
             }
+            let end_idx = (found_idx + 1..range.end)
+                .find(|&idx| {
+                    indent_of(&line_list[idx]).is_some_and(|indent| indent <= child_indent)
+                })
+                .unwrap_or(range.end);
+            range = found_idx + 1..end_idx;
+            child_indent += 2;
         } else {
-            bail!("failed to get {}-th element", idx);
-        }
-        if unset {
-            for page in self.iter_mut().skip(idx + 1) {
-                if let Some(value) = page.value() {
-                    page.set_value(Some(value - 1));
+            if value_follows || rest.starts_with(['|', '>']) {
+                return None;
+            }
+            match value {
+                Some(value) => {
+                    line_list[found_idx] = format!(
+                        "{}{}: {}{}{}{}",
+                        " ".repeat(child_indent),
+                        key_text,
+                        anchor.map_or(String::new(), |anchor| format!("&{} ", anchor)),
+                        emit_scalar(value)?,
+                        trailing_comment(rest),
+                        line_ending
+                    );
+                }
+                None => {
+                    line_list.remove(found_idx);
                 }
             }
-        } else {
-            self.get_mut(idx).unwrap().set_value(Some(pre_value + 1));
-            for page in self.iter_mut().skip(idx + 1) {
-                if let Some(value) = page.value() {
-                    page.set_value(Some(value + 1));
+        }
+    }
+    Some(line_list.concat())
+}
+
+/// Length of a key at the start of a line if it is the given segment, plain or quoted (e.g. `weight`, `"weight"` or `'weight'`) and followed by `:`.
+/// 行頭のキーが与えられた部分と一致し、そのまま、または引用符で囲まれて（`weight`、`"weight"`、`'weight'`など）`:`が続く場合のキーの長さ。
+fn key_text_len(line: &str, segment: &str) -> Option<usize> {
+    let key_len = match line.chars().next() {
+        Some(quote @ ('"' | '\'')) => line[1..]
+            .strip_prefix(segment)?
+            .starts_with(quote)
+            .then_some(segment.len() + 2)?,
+        _ => line.strip_prefix(segment).map(|_| segment.len())?,
+    };
+    line[key_len..].starts_with(':').then_some(key_len)
+}
+
+/// The comment at the end of a value on one line, with the spaces before it (e.g. ` # shown in the menu`), or an empty string.
+/// A `#` inside quotes or not following a space is a part of the value.
+/// 1行の値の末尾にあるコメント。前の空白を含む（` # shown in the menu`など）。なければ空文字列。
+/// 引用符の中や空白に続かない`#`は値の一部とする。
+fn trailing_comment(value: &str) -> &str {
+    // Skip a quoted scalar first, where `\"` and `''` escape the quotes.
+    // 先に引用符で囲まれたスカラーを飛ばす。`\"`と`''`は引用符のエスケープである。
+    let mut plain_start = 0;
+    if let Some(quote @ ('"' | '\'')) = value.chars().next() {
+        let mut char_list = value.char_indices().skip(1);
+        while let Some((idx, c)) = char_list.next() {
+            if quote == '"' && c == '\\' {
+                char_list.next();
+            } else if c == quote {
+                if quote == '\'' && value[idx + 1..].starts_with('\'') {
+                    char_list.next();
+                } else {
+                    plain_start = idx + 1;
+                    break;
                 }
             }
         }
-        Ok(())
     }
+    let mut prev = '\0';
+    for (idx, c) in value[plain_start..].char_indices() {
+        if c == '#' && prev.is_whitespace() {
+            let idx = plain_start + idx;
+            return &value[value[..idx].trim_end().len()..];
+        }
+        prev = c;
+    }
+    ""
+}
 
-    /// If both are not None, replace the value as well.
-    /// 両方Noneでなければvalueも入れ替える。
-    pub fn swap_with_value(&mut self, idx: usize, swap_direction: SwapDirection) -> Result<()> {
-        if idx >= self.len() {
-            bail!("failed to get {}-th element", idx);
-        } else {
-            match swap_direction {
-                SwapDirection::Prev => {
-                    if idx == 0 {
-                        bail!("failed to get previous one");
-                    }
+/// Whether a line comments out a key (e.g. `# weight: 3`, or `# weight = 3` in TOML), given the last segment of the key.
+/// 行がキーをコメントアウトしたもの（`# weight: 3`、TOMLでは`# weight = 3`など）かどうか。キーの最後の部分を与える。
+fn is_commented_key(line: &str, last_segment: &str, format: FrontMatterFormat) -> bool {
+    line.trim_start()
+        .strip_prefix('#')
+        .and_then(|line| line.trim_start().strip_prefix(last_segment))
+        .is_some_and(|line| line.trim_start().starts_with(format.key_separator()))
+}
+
+/// Remove the lines commenting out a key, matched by its last segment as on load.
+/// キーをコメントアウトした行を取り除く。読み込み時と同じくキーの最後の部分で照合する。
+fn remove_commented_key(frontmatter: &str, key: &str, format: FrontMatterFormat) -> String {
+    let last_segment = key.rsplit('.').next().unwrap();
+    frontmatter
+        .split_inclusive('\n')
+        .filter(|line| !is_commented_key(line, last_segment, format))
+        .collect()
+}
+
+/// A scalar written on one line as the emitter does, e.g. with quotes if needed.
+/// エミッタと同じように1行で書いたスカラー。必要なら引用符が付く。
+fn emit_scalar(scalar: &Yaml) -> Option<String> {
+    let mut text = String::new();
+    YamlEmitter::new(&mut text).dump(scalar).ok()?;
+    let text = text.strip_prefix("---")?.trim_start();
+    (!text.contains('\n')).then(|| text.to_owned())
+}
+
+/// Set a dot-separated key in TOML text by editing only its line, so that comments and the other lines are kept as they are.
+/// The key is matched under table headers as well as with dotted keys (e.g. `weight` under `[menu.main]` for `menu.main.weight`),
+/// and a missing key is added to the deepest table given by a header that it belongs to.
+/// Returns None if the key cannot be edited this way (e.g. its value is a multi-line string).
+/// TOMLのテキストでドット区切りのキーをその行だけ書き換えて設定し、コメントや他の行をそのまま保つ。
+/// ドット区切りのキーに加えてテーブルの見出しの下のキーにも一致させ（`menu.main.weight`に対する`[menu.main]`の下の`weight`など）、
+/// ないキーは、見出しで与えられたテーブルのうちキーが属する最も深いものに追加する。
+/// この方法で書き換えられない場合（値が複数行の文字列であるなど）はNoneを返す。
+fn edit_toml_line(toml: &str, key: &str, value: Option<&Yaml>) -> Option<String> {
+    let line_ending = if toml.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut line_list = toml
+        .split_inclusive('\n')
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    let segment_list = key.split('.').collect::<Vec<_>>();
+    // The table of each line, which is None in an array of tables, and whether the line has any content.
+    // 各行のテーブル（テーブルの配列の中ではNone）と、その行に中身があるかどうか。
+    let mut table_list = Vec::with_capacity(line_list.len());
+    let mut table = Some(Vec::new());
+    let mut found = None;
+    // A value continued on the following lines: the delimiter of a multi-line string, or the depth of brackets.
+    // 続く行に続く値。複数行の文字列の区切り、または括弧の深さ。
+    let mut string_delimiter = None;
+    let mut bracket_depth = 0;
+    for (idx, line) in line_list.iter().enumerate() {
+        let trimmed_line = line.trim();
+        if let Some(delimiter) = string_delimiter {
+            if line.matches(delimiter).count() % 2 == 1 {
+                string_delimiter = None;
+            }
+        } else if bracket_depth > 0 {
+            bracket_depth += bracket_balance(line);
+        } else if trimmed_line.starts_with("[[") {
+            table = None;
+        } else if let Some(header) = trimmed_line.strip_prefix('[') {
+            table = parse_toml_key(header, ']').map(|(segment_list, _)| segment_list);
+        } else if let Some((key_segment_list, separator_idx)) = parse_toml_key(line, '=') {
+            let rest = line[separator_idx + 1..].trim();
+            if let Some(delimiter) = ["\"\"\"", "'''"]
+                .into_iter()
+                .find(|delimiter| rest.starts_with(delimiter))
+            {
+                if rest.matches(delimiter).count() % 2 == 1 {
+                    string_delimiter = Some(delimiter);
                 }
-                SwapDirection::Next => {
-                    if idx == self.len() - 1 {
-                        bail!("failed to get next one");
-                    }
+            } else {
+                bracket_depth = bracket_balance(rest).max(0);
+            }
+            if let Some(table) = &table {
+                if table.iter().chain(&key_segment_list).eq(&segment_list) {
+                    found = Some((idx, separator_idx));
                 }
             }
         }
-        let idx_neighbor = match swap_direction {
-            SwapDirection::Prev => idx - 1,
-            SwapDirection::Next => idx + 1,
+        let has_content = !trimmed_line.is_empty() && !trimmed_line.starts_with('#');
+        table_list.push((table.clone(), has_content));
+    }
+    let Some((found_idx, separator_idx)) = found else {
+        // A missing key has nothing to remove.
+        // ないキーには取り除くものがない。
+        let Some(value) = value else {
+            return Some(line_list.concat());
         };
-        if self.get(idx).unwrap().value().is_some()
-            && self.get(idx_neighbor).unwrap().value().is_some()
-        {
-            let value_neighbor = self.get(idx_neighbor).unwrap().value().unwrap();
-            let value = self.get(idx).unwrap().value().unwrap();
-            self.get_mut(idx).unwrap().set_value(Some(value_neighbor));
-            self.get_mut(idx_neighbor).unwrap().set_value(Some(value));
+        let parent = table_list
+            .iter()
+            .filter_map(|(table, _)| table.as_ref())
+            .filter(|table| {
+                table.len() < segment_list.len()
+                    && table.iter().zip(&segment_list).all(|(a, b)| a == b)
+            })
+            .max_by_key(|table| table.len())
+            .cloned()
+            .unwrap_or_default();
+        let insert_idx = table_list
+            .iter()
+            .rposition(|(table, has_content)| *has_content && table.as_ref() == Some(&parent))
+            .map_or(0, |idx| idx + 1);
+        let key_text = segment_list[parent.len()..]
+            .iter()
+            .map(|segment| toml_key_text(segment))
+            .collect::<Vec<_>>()
+            .join(".");
+        if insert_idx > 0 && !line_list[insert_idx - 1].ends_with('\n') {
+            line_list[insert_idx - 1].push_str(line_ending);
         }
-        self.swap(idx, idx_neighbor);
-        Ok(())
+        line_list.insert(
+            insert_idx,
+            format!("{} = {}{}", key_text, emit_toml_scalar(value)?, line_ending),
+        );
+        return Some(line_list.concat());
+    };
+    let line = &line_list[found_idx];
+    let value_text = &line[separator_idx + 1..];
+    let value_start = separator_idx + 1 + value_text.len() - value_text.trim_start().len();
+    let rest = value_text.trim();
+    if rest.starts_with("\"\"\"") || rest.starts_with("'''") || rest.starts_with(['[', '{']) {
+        return None;
     }
-
-    /// Reflect the value in yaml.
-    /// yamlにvalueを反映させる。
-    pub fn substitute_value(&mut self) {
-        let key = self.key().clone();
-        for page in self.iter_mut() {
-            page.substitute_value(&key);
+    match value {
+        Some(value) => {
+            line_list[found_idx] = format!(
+                "{}{}{}{}",
+                &line[..value_start],
+                emit_toml_scalar(value)?,
+                trailing_comment(rest),
+                line_ending
+            );
+        }
+        None => {
+            line_list.remove(found_idx);
         }
     }
+    Some(line_list.concat())
+}
 
-    pub fn overwrite_frontmatter(&mut self) -> Result<()> {
+/// Segments of a TOML key (e.g. `menu."main".weight`) at the start of a text, and the index of the character ending it (e.g. `=`).
+/// Returns None if the text does not start with a key followed by the character.
+/// テキストの先頭にあるTOMLのキー（`menu."main".weight`など）の各部分と、それを終える文字（`=`など）の位置。
+/// テキストがキーとその文字で始まっていなければNoneを返す。
+fn parse_toml_key(text: &str, end: char) -> Option<(Vec<String>, usize)> {
+    fn skip_space(text: &str, idx: usize) -> usize {
+        text.len() - text[idx..].trim_start_matches([' ', '\t']).len()
+    }
+    let mut segment_list = Vec::new();
+    let mut idx = skip_space(text, 0);
+    loop {
+        let rest = &text[idx..];
+        let segment_len = match rest.chars().next()? {
+            quote @ ('"' | '\'') => {
+                let close_idx = rest[1..].find(quote)? + 1;
+                segment_list.push(rest[1..close_idx].to_owned());
+                close_idx + 1
+            }
+            _ => {
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                    .unwrap_or(rest.len());
+                if len == 0 {
+                    return None;
+                }
+                segment_list.push(rest[..len].to_owned());
+                len
+            }
+        };
+        idx = skip_space(text, idx + segment_len);
+        match text[idx..].chars().next()? {
+            '.' => idx = skip_space(text, idx + 1),
+            c if c == end => return Some((segment_list, idx)),
+            _ => return None,
+        }
+    }
+}
+
+/// Brackets opened minus brackets closed in a line, ignoring those in strings, to follow an array spanning several lines.
+/// 文字列の中を除いた、行で開いた括弧の数から閉じた括弧の数を引いたもの。複数行にわたる配列をたどるために使う。
+fn bracket_balance(line: &str) -> i32 {
+    let mut balance = 0;
+    let mut quote = None;
+    let mut char_list = line.chars();
+    while let Some(c) = char_list.next() {
+        match (quote, c) {
+            (Some('"'), '\\') => {
+                char_list.next();
+            }
+            (Some(quote_char), c) if c == quote_char => quote = None,
+            (Some(_), _) => (),
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#') => break,
+            (None, '[' | '{') => balance += 1,
+            (None, ']' | '}') => balance -= 1,
+            (None, _) => (),
+        }
+    }
+    balance
+}
+
+/// A segment of a key as it is written in TOML, quoted unless it is a bare key.
+/// TOMLに書くキーの部分。裸のキーでなければ引用符で囲む。
+fn toml_key_text(segment: &str) -> String {
+    if !segment.is_empty()
+        && segment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        segment.to_owned()
+    } else {
+        toml::Value::String(segment.to_owned()).to_string()
+    }
+}
+
+/// A scalar written as a TOML value, e.g. a string with quotes.
+/// TOMLの値として書いたスカラー。例えば文字列は引用符で囲む。
+fn emit_toml_scalar(scalar: &Yaml) -> Option<String> {
+    match scalar {
+        Yaml::Integer(x) => Some(x.to_string()),
+        Yaml::Real(x) => Some(x.to_owned()),
+        Yaml::Boolean(x) => Some(x.to_string()),
+        Yaml::String(x) => Some(toml::Value::String(x.to_owned()).to_string()),
+        _ => None,
+    }
+}
+
+/// Read TOML as YAML, so that FrontMatter in either format is handled in the same way.
+/// Dates and times are read as strings, and floats as TOML writes them.
+/// TOMLをYAMLとして読み、どちらの形式のFrontMatterも同じように扱えるようにする。
+/// 日付と時刻は文字列として、浮動小数点数はTOMLが書く通りに読む。
+fn load_toml(toml: &str) -> Result<Yaml> {
+    fn to_yaml(value: toml::Value) -> Yaml {
+        match value {
+            toml::Value::String(x) => Yaml::String(x),
+            toml::Value::Integer(x) => Yaml::Integer(x),
+            float @ toml::Value::Float(_) => Yaml::Real(float.to_string()),
+            toml::Value::Boolean(x) => Yaml::Boolean(x),
+            toml::Value::Datetime(x) => Yaml::String(x.to_string()),
+            toml::Value::Array(x) => Yaml::Array(x.into_iter().map(to_yaml).collect()),
+            toml::Value::Table(x) => Yaml::Hash(
+                x.into_iter()
+                    .map(|(key, value)| (Yaml::String(key), to_yaml(value)))
+                    .collect(),
+            ),
+        }
+    }
+    Ok(to_yaml(toml::Value::Table(toml.parse()?)))
+}
+
+/// YAML with the keys of every mapping sorted, to compare mappings regardless of the order of their keys.
+/// 全てのマッピングのキーをソートしたYAML。キーの順序によらずにマッピングを比較するために使う。
+fn sort_key(yaml: Yaml) -> Yaml {
+    match yaml {
+        Yaml::Hash(hash) => {
+            let mut entry_list = hash
+                .into_iter()
+                .map(|(key, value)| (key, sort_key(value)))
+                .collect::<Vec<_>>();
+            entry_list.sort_by(|a, b| a.0.cmp(&b.0));
+            Yaml::Hash(entry_list.into_iter().collect())
+        }
+        Yaml::Array(array) => Yaml::Array(array.into_iter().map(sort_key).collect()),
+        yaml => yaml,
+    }
+}
+
+/// Name of the type of YAML, as the variant of `Yaml`.
+/// YAMLの型の名前。`Yaml`のバリアント名を使う。
+fn yaml_type_name(yaml: &Yaml) -> &'static str {
+    match yaml {
+        Yaml::Real(_) => "Real",
+        Yaml::Integer(_) => "Integer",
+        Yaml::String(_) => "String",
+        Yaml::Boolean(_) => "Boolean",
+        Yaml::Array(_) => "Array",
+        Yaml::Hash(_) => "Hash",
+        Yaml::Alias(_) => "Alias",
+        Yaml::Null => "Null",
+        Yaml::BadValue => "BadValue",
+    }
+}
+
+/// Lowercase a name and replace whitespace with hyphens, e.g. `Getting Started` becomes `getting-started`.
+/// 名前を小文字にし、空白をハイフンに置き換える。例えば`Getting Started`は`getting-started`になる。
+fn slugify(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase()
+}
+
+/// Separators of paths on the platform, used to split paths for display.
+/// 表示のためにパスを分割する、プラットフォームのパスの区切り文字。
+#[cfg(windows)]
+pub(crate) const SEPARATOR_LIST: &[char] = &['\\', '/'];
+#[cfg(not(windows))]
+pub(crate) const SEPARATOR_LIST: &[char] = &['/'];
+
+/// Split a path as text into its directory and file name at the last separator.
+/// A separator right after the root (e.g. `/` or `C:\\`) stays with the directory.
+/// テキストとしてのパスを最後の区切り文字でディレクトリとファイル名に分割する。
+/// ルートの直後の区切り文字（`/`や`C:\\`など）はディレクトリに残す。
+pub(crate) fn split_path_text<'a>(path: &'a str, separator_list: &[char]) -> (&'a str, &'a str) {
+    match path.rfind(separator_list) {
+        Some(idx) => {
+            let dir = &path[..idx];
+            if dir.trim_end_matches(separator_list).is_empty() || dir.ends_with(':') {
+                (&path[..idx + 1], &path[idx + 1..])
+            } else {
+                (dir, &path[idx + 1..])
+            }
+        }
+        None => ("", path),
+    }
+}
+
+/// The rest of a directory as text below a base directory, without leading separators,
+/// or None if the directory is not below (or the same as) the base.
+/// テキストとしてのディレクトリの、基準のディレクトリより下の残りの部分。先頭の区切り文字は除く。
+/// ディレクトリが基準より下（または同じ）でなければNone。
+pub(crate) fn strip_dir_text<'a>(
+    dir: &'a str,
+    base: &str,
+    separator_list: &[char],
+) -> Option<&'a str> {
+    let base = match base.trim_end_matches(separator_list) {
+        "" => base,
+        trimmed_base => trimmed_base,
+    };
+    let rest = dir.strip_prefix(base)?;
+    if rest.is_empty() || rest.starts_with(separator_list) || base.ends_with(separator_list) {
+        Some(rest.trim_start_matches(separator_list))
+    } else {
+        None
+    }
+}
+
+/// A scalar value as it is written in FrontMatter.
+/// FrontMatterに書かれている通りのスカラー値。
+fn scalar_text(scalar: &Yaml) -> String {
+    match scalar {
+        Yaml::String(x) | Yaml::Real(x) => x.to_owned(),
+        Yaml::Integer(x) => x.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// A value in FrontMatter as it is shown in the list, e.g. a title written as a number or a list.
+/// A scalar is shown as it is written, and a list or a map by a placeholder.
+/// 一覧に表示する、FrontMatterの値。例えば数値やリストで書かれたタイトル。
+/// スカラーは書かれている通りに、リストやマップはプレースホルダーで表示する。
+fn yaml_to_display_string(yaml: &Yaml) -> Option<String> {
+    match yaml {
+        Yaml::String(x) | Yaml::Real(x) => Some(x.to_owned()),
+        Yaml::Integer(x) => Some(x.to_string()),
+        Yaml::Boolean(x) => Some(x.to_string()),
+        Yaml::Array(_) => Some("[list]".to_owned()),
+        Yaml::Hash(_) => Some("[map]".to_owned()),
+        Yaml::Alias(_) | Yaml::Null | Yaml::BadValue => None,
+    }
+}
+
+/// A value found in FrontMatter for an error message: a string is quoted, and a value which is not a scalar is named by its type.
+/// エラーメッセージのための、FrontMatterで見つかった値。文字列は引用符で囲み、スカラーでない値は型の名前で示す。
+fn found_text(found: &Yaml) -> String {
+    match found {
+        Yaml::String(x) => format!("'{}'", x),
+        Yaml::Real(x) => x.to_owned(),
+        Yaml::Boolean(x) => x.to_string(),
+        found => yaml_type_name(found).to_owned(),
+    }
+}
+
+/// Compare strings treating runs of digits as numbers, so that `1.10.0` comes after `1.9.0`.
+/// 数字の並びを数として扱って文字列を比較する。`1.10.0`は`1.9.0`の後になる。
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    fn chunk_list(x: &str) -> Vec<&str> {
+        let mut chunk_list = Vec::new();
+        let mut chunk_start = 0;
+        for (idx, c) in x.char_indices().skip(1) {
+            let prev_is_digit = x[..idx].chars().next_back().unwrap().is_ascii_digit();
+            if c.is_ascii_digit() != prev_is_digit {
+                chunk_list.push(&x[chunk_start..idx]);
+                chunk_start = idx;
+            }
+        }
+        if chunk_start < x.len() {
+            chunk_list.push(&x[chunk_start..]);
+        }
+        chunk_list
+    }
+    for (chunk_a, chunk_b) in chunk_list(a).into_iter().zip(chunk_list(b)) {
+        let ordering = if chunk_a.starts_with(|c: char| c.is_ascii_digit())
+            && chunk_b.starts_with(|c: char| c.is_ascii_digit())
+        {
+            let digit_a = chunk_a.trim_start_matches('0');
+            let digit_b = chunk_b.trim_start_matches('0');
+            digit_a
+                .len()
+                .cmp(&digit_b.len())
+                .then_with(|| digit_a.cmp(digit_b))
+        } else {
+            chunk_a.cmp(chunk_b)
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    chunk_list(a)
+        .len()
+        .cmp(&chunk_list(b).len())
+        .then_with(|| a.cmp(b))
+}
+
+/// Parse a duration before now (e.g. `7d`, `2h`) or a date in UTC (e.g. `2023-01-31`).
+/// 現在からの期間（例: `7d`, `2h`）またはUTCの日付（例: `2023-01-31`）を解釈する。
+fn parse_since(since: &str) -> Result<SystemTime> {
+    if let Ok(duration) = humantime::parse_duration(since) {
+        return SystemTime::now()
+            .checked_sub(duration)
+            .with_context(|| format!("failed to go back {} from now", since));
+    }
+    humantime::parse_rfc3339_weak(since)
+        .or_else(|_| humantime::parse_rfc3339_weak(&format!("{} 00:00:00", since)))
+        .with_context(|| format!("failed to parse {} as a duration or a date", since))
+}
+
+/// Parse a date in FrontMatter such as `2023-01-31`, `2023-01-31 10:00` or `2023-01-31T10:00:00+09:00`.
+/// Fractions of a second and time zones are ignored.
+/// `2023-01-31`、`2023-01-31 10:00`、`2023-01-31T10:00:00+09:00`のようなFrontMatterの日付を解析する。
+/// 秒の端数とタイムゾーンは無視する。
+fn parse_date(date: &str) -> Option<SystemTime> {
+    let date = date.trim();
+    let (day, time) = match date.split_once(['T', ' ']) {
+        Some((day, time)) => (day, time.trim()),
+        None => (date, ""),
+    };
+    let time = time
+        .split(['.', '+', '-', 'Z', ' '])
+        .next()
+        .unwrap_or_default();
+    let time = match time.len() {
+        0 => "00:00:00".to_owned(),
+        5 => format!("{}:00", time),
+        _ => time.to_owned(),
+    };
+    humantime::parse_rfc3339_weak(&format!("{} {}", day, time)).ok()
+}
+
+/// Index of the tier a value belongs to: the last one whose base does not exceed it.
+/// Values below the first base belong to the first tier.
+/// 値が属する階層の位置。基準値が値を超えない最後の階層で、最初の基準値より小さい値は最初の階層に属する。
+fn tier_idx(tier_list: &[Tier], value: i64) -> usize {
+    tier_list
+        .iter()
+        .rposition(|tier| tier.base <= value)
+        .unwrap_or(0)
+}
+
+/// Rank of a page by its pin, by which pages having values are sorted before anything else.
+/// ページの固定による順位。値を持つページは何よりも先にこれでソートされる。
+fn pin_rank(pin: &Option<Pin>) -> u8 {
+    match pin {
+        Some(Pin::First) => 0,
+        None => 1,
+        Some(Pin::Last) => 2,
+    }
+}
+
+/// Format of FrontMatter.
+///
+/// FrontMatterの形式。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum FrontMatterFormat {
+    /// YAML between a `---` line and a `---` (or `...`) line.
+    /// `---`の行と`---`（または`...`）の行の間のYAML。
+    Yaml,
+    /// TOML between `+++` lines.
+    /// `+++`の行の間のTOML。
+    Toml,
+}
+
+impl FrontMatterFormat {
+    /// Line opening FrontMatter, which closes it as well.
+    /// FrontMatterを始める行。FrontMatterを終える行でもある。
+    fn delimiter(self) -> &'static str {
+        match self {
+            Self::Yaml => "---",
+            Self::Toml => "+++",
+        }
+    }
+    /// Character between a key and its value.
+    /// キーと値の間の文字。
+    fn key_separator(self) -> char {
+        match self {
+            Self::Yaml => ':',
+            Self::Toml => '=',
+        }
+    }
+}
+
+impl FromStr for FrontMatterFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(format: &str) -> Result<Self> {
+        match format {
+            "yaml" => Ok(Self::Yaml),
+            "toml" => Ok(Self::Toml),
+            _ => bail!("unknown format of front matter (yaml or toml): {}", format),
+        }
+    }
+}
+
+/// Split the content of a file into the format of its FrontMatter, the FrontMatter without its delimiters, and the body.
+/// A leading UTF-8 BOM is ignored, and so are blank lines and HTML comments before FrontMatter.
+/// This is the same splitting as the one used to read and write pages.
+/// ファイルの内容を、FrontMatterの形式、区切りを除いたFrontMatter、本文に分割する。
+/// 先頭のUTF-8のBOMと、FrontMatterの前にある空行とHTMLのコメントは無視する。
+/// ページの読み書きと同じ分割を行う。
+///
+/// ```
+/// # use order_in_yaml_frontmatter::page::{split_frontmatter, FrontMatterFormat};
+/// let (format, frontmatter, body) = split_frontmatter("---\nweight: 1\n---\nbody\n").unwrap();
+/// assert_eq!(format, FrontMatterFormat::Yaml);
+/// assert_eq!(frontmatter, "weight: 1\n");
+/// assert_eq!(body, "body\n");
+///
+/// let (format, frontmatter, _) = split_frontmatter("+++\nweight = 1\n+++\nbody\n").unwrap();
+/// assert_eq!(format, FrontMatterFormat::Toml);
+/// assert_eq!(frontmatter, "weight = 1\n");
+///
+/// // Only the first closing line counts, so dashes in the body (e.g. of MDX) are left alone.
+/// let content = "---\ntitle: A\n---\n<Note>\n---\n</Note>\n```\n---\n```\n";
+/// let (_, frontmatter, body) = split_frontmatter(content).unwrap();
+/// assert_eq!(frontmatter, "title: A\n");
+/// assert_eq!(body, "<Note>\n---\n</Note>\n```\n---\n```\n");
+///
+/// // Blank lines and HTML comments before the opening line are skipped.
+/// let content = "<!--\n  SPDX-License-Identifier: MIT\n-->\n\n---\nweight: 1\n---\nbody\n";
+/// let (_, frontmatter, body) = split_frontmatter(content).unwrap();
+/// assert_eq!(frontmatter, "weight: 1\n");
+/// assert_eq!(body, "body\n");
+/// ```
+pub fn split_frontmatter(content: &str) -> Result<(FrontMatterFormat, &str, &str)> {
+    let content = content.strip_prefix(Page::BOM).unwrap_or(content);
+    let block = split_frontmatter_block(content, false).context("failed to get front matter")?;
+    Ok((block.format, block.frontmatter, block.body))
+}
+
+/// Split a file at its FrontMatter, which is YAML opening with `---` and closing with `---` or `...`,
+/// or TOML between `+++` lines. If `dashes_only` is true, only `---` closes YAML.
+/// Only the first closing line ends FrontMatter, and everything after it is the body as it is.
+/// Blank lines and HTML comments before the opening line are kept as a prefix.
+/// ファイルをFrontMatterで分割する。FrontMatterは`---`で始まり`---`または`...`で終わるYAML、
+/// または`+++`の行の間のTOMLである。`dashes_only`が真の場合、YAMLは`---`だけで終わる。
+/// 最初の閉じる行だけがFrontMatterを終わらせ、その後は全てそのまま本文とする。
+/// 始まりの行より前にある空行とHTMLのコメントは前置きとして残す。
+fn split_frontmatter_block(content: &str, dashes_only: bool) -> Option<FrontMatterBlock<'_>> {
+    let (prefix, content) = content.split_at(prefix_len(content));
+    let mut line_iter = content.split_inclusive('\n');
+    let opening_line = line_iter.next()?;
+    let format = [FrontMatterFormat::Yaml, FrontMatterFormat::Toml]
+        .into_iter()
+        .find(|format| opening_line.trim_end() == format.delimiter())?;
+    let frontmatter_start = opening_line.len();
+    let mut closing_start = frontmatter_start;
+    for line in line_iter {
+        let trimmed_line = line.trim_end();
+        if trimmed_line == format.delimiter()
+            || (format == FrontMatterFormat::Yaml && !dashes_only && trimmed_line == "...")
+        {
+            let frontmatter = &content[frontmatter_start..closing_start];
+            let trailing_space = &frontmatter[frontmatter.trim_end().len()..];
+            let closing_prefix = trailing_space
+                .strip_prefix("\r\n")
+                .or_else(|| trailing_space.strip_prefix('\n'))
+                .unwrap_or(trailing_space);
+            return Some(FrontMatterBlock {
+                format,
+                prefix,
+                opening_line,
+                frontmatter,
+                closing_prefix,
+                closing_line: line,
+                body: &content[closing_start + line.len()..],
+            });
+        }
+        closing_start += line.len();
+    }
+    None
+}
+
+/// Length of blank lines and HTML comments (e.g. a license) before FrontMatter.
+/// An unclosed comment is not skipped.
+/// FrontMatterの前にある空行とHTMLのコメント（ライセンスなど）の長さ。
+/// 閉じていないコメントは飛ばさない。
+fn prefix_len(content: &str) -> usize {
+    let mut prefix_len = 0;
+    let mut comment_len = None;
+    for line in content.split_inclusive('\n') {
+        let trimmed_line = line.trim();
+        if let Some(len) = comment_len {
+            if trimmed_line.ends_with("-->") {
+                prefix_len += len + line.len();
+                comment_len = None;
+            } else {
+                comment_len = Some(len + line.len());
+            }
+        } else if trimmed_line.is_empty() {
+            prefix_len += line.len();
+        } else if trimmed_line.starts_with("<!--") {
+            if trimmed_line.len() > "<!--".len() && trimmed_line.ends_with("-->") {
+                prefix_len += line.len();
+            } else {
+                comment_len = Some(line.len());
+            }
+        } else {
+            break;
+        }
+    }
+    prefix_len
+}
+
+impl Deref for PageList {
+    type Target = Vec<Page>;
+    fn deref(&self) -> &Self::Target {
+        &self.page_list
+    }
+}
+impl DerefMut for PageList {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.page_list
+    }
+}
+
+impl PageList {
+    const OVERFLOW_MESSAGE: &'static str = "order value overflow";
+
+    pub fn try_new(config: &Config) -> Result<Self> {
+        if config.key.is_empty() {
+            bail!("no key is given");
+        }
+        let target_dir = config.target.as_path();
+        if !target_dir.exists() {
+            if !config.create_target {
+                bail!("target directory does not exist: {}", target_dir.display());
+            }
+            fs::create_dir_all(target_dir)
+                .with_context(|| format!("failed to create {}", target_dir.display()))?;
+        }
+        let recursive = config.recursive;
+        // If a file is given, its siblings are listed instead.
+        // ファイルが与えられた場合は、同じディレクトリのファイルを対象にする。
+        let (target_dir, recursive) = if target_dir.is_file() {
+            match target_dir.parent() {
+                Some(parent) if parent != Path::new("") => (parent, false),
+                _ => (Path::new("."), false),
+            }
+        } else {
+            (target_dir, recursive)
+        };
+        let mut page_list = Self {
+            page_list: Vec::new(),
+            key_list: config
+                .key
+                .iter()
+                .map(|key| key.split('@').next().unwrap().to_owned())
+                .collect(),
+            category_list: config
+                .key
+                .iter()
+                .map(|key| key.split_once('@').map(|(_, category)| category.to_owned()))
+                .collect(),
+            key_idx: 0,
+            filter_tag: config.filter_tag.clone(),
+            skip_draft: config
+                .skip_draft
+                .map(|draft_mode| (config.draft_key.clone(), draft_mode)),
+            group_by_dir: config.group_by_dir,
+            tier_list: {
+                let mut tier_list = config.tier.clone();
+                tier_list.sort_by_key(|tier| tier.base);
+                tier_list
+            },
+            value_as_string: config.value_as_string,
+            keep_quoted: config.keep_quoted,
+            one_based: config.one_based,
+            extension_list: config.extension.clone(),
+            verbatim_body_list: config.verbatim_body.clone(),
+            ext_format_list: config.ext_format.clone(),
+            format: config.format,
+            since: config.since.as_deref().map(parse_since).transpose()?,
+            limit: config.limit,
+            output_dir: config.output_dir.clone(),
+            from_filename: config
+                .from_filename
+                .as_deref()
+                .map(Regex::new)
+                .transpose()?,
+            clean_excluded: config.clean_excluded,
+            normalize: config.normalize,
+            unassigned_first: config.unassigned_first,
+            renumber_on_load: config.renumber_on_load,
+            key_type: config.key_type,
+            traversal: config.traversal,
+            target_dir: target_dir.to_owned(),
+            require_key: config.require_key,
+            slug_order: config.slug_order,
+            missing_list: Vec::new(),
+            non_utf8_list: Vec::new(),
+            unreadable_list: Vec::new(),
+        };
+        if page_list.key_type == KeyType::String && page_list.from_filename.is_some() {
+            bail!("values cannot be taken from file names when keys are strings");
+        }
+        if page_list.key_type == KeyType::String && !page_list.tier_list.is_empty() {
+            bail!("tiers cannot be used when keys are strings");
+        }
+        if page_list.key_type == KeyType::String && page_list.one_based {
+            bail!("values cannot be written from 1 when keys are strings");
+        }
+        if let Some(tier_pair) = page_list
+            .tier_list
+            .windows(2)
+            .find(|tier_pair| tier_pair[0].base == tier_pair[1].base)
+        {
+            bail!(
+                "tiers {} and {} share the base value {}",
+                tier_pair[0].name,
+                tier_pair[1].name,
+                tier_pair[0].base
+            );
+        }
+        let depth = if recursive { config.max_depth } else { Some(0) };
+        page_list.append_page_list(target_dir, depth)?;
+        page_list.dedup_by_path()?;
+        page_list.locate_real_path(target_dir)?;
+        if page_list.require_key {
+            page_list.check_required_key()?;
+        }
+        // Every key is normalized, and the first one is left to be reordered.
+        // 全てのキーを正規化し、最初のキーを並べ替えの対象にする。
+        for key_idx in (0..config.key.len()).rev() {
+            page_list.set_key_idx(key_idx);
+            match page_list.key_type {
+                KeyType::Integer => page_list.sort_and_fix()?,
+                KeyType::String => page_list.reorder_by(|a, b| {
+                    natural_cmp(&a.scalar_text().unwrap(), &b.scalar_text().unwrap())
+                })?,
+            }
+        }
+        if page_list.key_type == KeyType::String {
+            // Ranks are not written, so pages are unchanged until they are moved.
+            // 順位は書き込まれないため、動かすまではページは変更されていない。
+            for page in page_list.iter_mut() {
+                page.value_old_list = page.value_list.clone();
+            }
+        }
+        for page in page_list.iter_mut() {
+            page.value_load_list = page.value_list.clone();
+        }
+        Ok(page_list)
+    }
+
+    /// The first of the candidate keys that some file has a value of.
+    /// A candidate whose value cannot be read as the type of keys is skipped.
+    /// 候補のキーのうち、いずれかのファイルが値を持つ最初のもの。
+    /// 値をキーの型として読めない候補は飛ばす。
+    pub fn detect_key(config: &Config) -> Result<String> {
+        for candidate in &config.key_candidates {
+            let config = Config {
+                key: vec![candidate.clone()],
+                require_key: false,
+                from_filename: None,
+                ..config.clone()
+            };
+            let page_list = Self::try_new(&config)?;
+            if page_list.unreadable_list.is_empty()
+                && page_list
+                    .iter()
+                    .any(|page| page.value_old_list[0].is_some())
+            {
+                return Ok(candidate.clone());
+            }
+        }
+        bail!(
+            "none of the keys {} has values in files; specify one with --key",
+            config.key_candidates.join(", ")
+        )
+    }
+
+    /// Add page lists
+    /// Subdirectories are walked as soon as they are found (depth-first)
+    /// or after all files in the directory (breadth-first).
+    /// `depth` is the number of levels of subdirectories left to walk, and None means no limit.
+    /// Entries of a directory are handled in the order of their paths.
+    /// Walking stops once as many pages as the limit are loaded.
+    /// Entries left to handle are kept in a stack instead of recursion, so that deep trees cannot overflow the call stack,
+    /// and a directory reached again (e.g. through a symbolic link making a loop) is walked only once.
+    /// ページリストを追加する。
+    /// サブディレクトリは見つけた時点で（深さ優先）、またはディレクトリ内の全ファイルの後で（幅優先）たどる。
+    /// `depth`はたどるサブディレクトリの残りの階層数で、Noneは無制限を表す。
+    /// ディレクトリ内のエントリはパスの順に扱う。
+    /// 上限の数だけページを読み込んだら、たどるのをやめる。
+    /// 残りのエントリは再帰ではなくスタックに積むため、深いツリーでも呼び出しスタックがあふれない。
+    /// また、再び行き着いたディレクトリ（ループするシンボリックリンクなど）は一度だけたどる。
+    fn append_page_list(&mut self, target_dir: &Path, depth: Option<usize>) -> Result<()> {
+        let mut walked_dir_set = HashSet::new();
+        // Entries are popped from the end, so they are pushed in reverse order.
+        // エントリは末尾から取り出すため、逆順に積む。
+        let mut entry_stack = vec![(target_dir.to_owned(), depth)];
+        while let Some((path, depth)) = entry_stack.pop() {
+            if self.is_full() {
+                return Ok(());
+            }
+            if path.is_dir() {
+                if !walked_dir_set.insert(fs::canonicalize(&path)?) {
+                    continue;
+                }
+                // The order of `read_dir` depends on the platform and the file system.
+                // `read_dir`の順序はプラットフォームやファイルシステムに依存する。
+                let mut path_list = path
+                    .read_dir()
+                    .with_context(|| format!("faild to open {}", path.display()))?
+                    .map(|entry_result| entry_result.map(|entry| entry.path()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                path_list.sort();
+                if depth == Some(0) {
+                    path_list.retain(|path| !path.is_dir());
+                } else if self.traversal == Traversal::BreadthFirst {
+                    // A stable sort keeps the order of paths among files and among directories.
+                    // 安定ソートのため、ファイル同士とディレクトリ同士のパスの順序は保たれる。
+                    path_list.sort_by_key(|path| path.is_dir());
+                }
+                let depth = depth.map(|depth| depth.saturating_sub(1));
+                entry_stack.extend(path_list.into_iter().rev().map(|path| (path, depth)));
+            } else if path.is_file()
+                && self
+                    .extension_list
+                    .iter()
+                    .any(|extension| path.extension() == Some(OsStr::new(extension)))
+            {
+                self.append_page(&path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the page of a file unless it is left out, e.g. for its modification time or its categories.
+    /// 更新時刻やカテゴリなどで除かれない限り、ファイルのページを追加する。
+    fn append_page(&mut self, path: &Path) -> Result<()> {
+        if let Some(since) = self.since {
+            if fs::metadata(path)?.modified()? < since {
+                return Ok(());
+            }
+        }
+        match Page::try_new(
+            path,
+            &self.key_list,
+            &self.category_list,
+            self.filter_tag.as_deref(),
+            self.skip_draft
+                .as_ref()
+                .map(|(draft_key, draft_mode)| (draft_key.as_str(), *draft_mode)),
+            self.key_type,
+            self.read_option(path),
+        ) {
+            Ok(mut page) => {
+                if self.one_based {
+                    page.shift_from_one_based();
+                }
+                if let Ok(relative_path) = path.strip_prefix(&self.target_dir) {
+                    page.relative_path = relative_path.to_owned();
+                }
+                if let Some(regex) = &self.from_filename {
+                    page.seed_value_from_filename(regex)?;
+                }
+                self.push(page)
+            }
+            Err(PageError::NoFrontMatter(path)) if self.require_key => self.missing_list.push(path),
+            Err(
+                PageError::NoFrontMatter(_) | PageError::OutOfCategory(_) | PageError::Draft(_),
+            ) => (),
+            Err(PageError::NonUtf8(path)) => self.non_utf8_list.push(path),
+            Err(
+                err @ (PageError::NoIntegerKey { .. }
+                | PageError::NoScalarKey { .. }
+                | PageError::NonScalarKey { .. }),
+            ) => self.unreadable_list.push(err.to_string()),
+            Err(err) => return Err(err.into()),
+        }
+        Ok(())
+    }
+
+    /// Settings for reading a file, given by its extension.
+    /// Files not given a format by `--ext-format` are read in the one of `--format`.
+    /// ファイルを読むときの設定。拡張子によって決まる。
+    /// `--ext-format`で形式を与えられていないファイルは`--format`の形式で読む。
+    fn read_option(&self, path: &Path) -> ReadOption {
+        let has_extension = |extension: &String| path.extension() == Some(OsStr::new(extension));
+        ReadOption {
+            verbatim_body: self.verbatim_body_list.iter().any(has_extension),
+            format: self
+                .ext_format_list
+                .iter()
+                .rfind(|ext_format| has_extension(&ext_format.extension))
+                .map_or(self.format.format(), |ext_format| Some(ext_format.format)),
+        }
+    }
+
+    /// Whether as many pages as the limit are loaded.
+    /// 上限の数だけページが読み込まれたかどうか。
+    fn is_full(&self) -> bool {
+        self.limit.is_some_and(|limit| self.len() >= limit)
+    }
+
+    /// Fail listing files without FrontMatter or without a value of a key that applies to them.
+    /// FrontMatterがないファイルや、適用されるキーの値がないファイルを挙げて失敗する。
+    fn check_required_key(&mut self) -> Result<()> {
+        let mut missing_list = std::mem::take(&mut self.missing_list);
+        missing_list.extend(
+            self.iter()
+                .filter(|page| {
+                    page.value_list
+                        .iter()
+                        .zip(&page.in_scope_list)
+                        .any(|(value, in_scope)| *in_scope && value.is_none())
+                })
+                .map(|page| page.path().to_owned()),
+        );
+        if missing_list.is_empty() {
+            return Ok(());
+        }
+        missing_list.sort();
+        let mut message = String::from("front matter or keys are missing in the following files:");
+        for path in missing_list {
+            write!(message, "\n  {}", path.display())?;
+        }
+        bail!(message)
+    }
+
+    /// Record which files are reached through symbolic links and which of them are outside the target directory.
+    /// シンボリックリンクを経由するファイルと、そのうち対象ディレクトリの外にあるファイルを記録する。
+    fn locate_real_path(&mut self, target_dir: &Path) -> Result<()> {
+        let canonical_target_dir = fs::canonicalize(target_dir)
+            .with_context(|| format!("failed to canonicalize {}", target_dir.display()))?;
         for page in self.iter_mut() {
-            page.overwrite_frontmatter()?;
+            let relative_path = page.path.strip_prefix(target_dir).unwrap_or(&page.path);
+            page.symlinked = relative_path.ancestors().any(|ancestor| {
+                ancestor != Path::new("")
+                    && fs::symlink_metadata(target_dir.join(ancestor))
+                        .is_ok_and(|metadata| metadata.file_type().is_symlink())
+            });
+            page.outside_target = page.symlinked
+                && fs::canonicalize(&page.path)
+                    .is_ok_and(|path| !path.starts_with(&canonical_target_dir));
+        }
+        Ok(())
+    }
+
+    /// Number of files outside the target directory that saving would write.
+    /// None are written when copies go to the output directory.
+    /// 保存によって書き込まれる、対象ディレクトリの外にあるファイルの数。
+    /// コピーを出力ディレクトリに書き込む場合は0になる。
+    pub fn outside_change_count(&self) -> usize {
+        if self.output_dir.is_some() {
+            return 0;
+        }
+        self.iter()
+            .filter(|page| {
+                page.outside_target && page.is_rewritten(self.clean_excluded, self.normalize)
+            })
+            .count()
+    }
+
+    /// Remove pages whose files are already listed (e.g. reached again through a symbolic link).
+    /// Files are compared by their canonicalized paths and the first one is kept.
+    /// 既に含まれているファイルのページを取り除く（シンボリックリンク経由など）。
+    /// ファイルは正規化したパスで比較し、最初のものを残す。
+    fn dedup_by_path(&mut self) -> Result<()> {
+        let mut canonical_path_set = HashSet::new();
+        let mut page_list = Vec::with_capacity(self.len());
+        for page in self.page_list.drain(..) {
+            let canonical_path = fs::canonicalize(page.path())
+                .with_context(|| format!("failed to canonicalize {}", page.path().display()))?;
+            if canonical_path_set.insert(canonical_path) {
+                page_list.push(page);
+            }
+        }
+        self.page_list = page_list;
+        Ok(())
+    }
+
+    /// Sort and assign sequential numbers beginning with 0.
+    /// None is greater than Some, or less if unassigned pages come first.
+    /// If pages are grouped by directory, numbers restart in each directory.
+    /// Unless pages are renumbered on load, values are kept and only raised where they are not ascending (e.g. shared).
+    /// ソートして0始まりの連番を割り当てる。NoneはSomeと比較すると大きく、未割り当てのページを先にする場合は小さい。
+    /// ディレクトリごとにまとめる場合は、ディレクトリごとに連番を振り直す。
+    /// 読み込み時に番号を振り直さない場合は値を保ち、昇順になっていない（重複など）ところだけ値を上げる。
+    pub fn sort_and_fix(&mut self) -> Result<()> {
+        if self.renumber_on_load {
+            self.reorder_by(|a, b| a.value().cmp(b.value()))
+        } else {
+            self.sort_pages(|a, b| a.value().cmp(b.value()));
+            // New values are computed first, so that pages are renumbered from 0 instead
+            // if raising values would overflow (e.g. values near the largest integer).
+            // 新しい値を先に計算し、値を上げるとオーバーフローする場合（最大の整数に近い値など）は代わりに0から振り直す。
+            let mut value_list = Vec::with_capacity(self.len());
+            let mut last_value: Option<(usize, i64)> = None;
+            for idx in 0..self.len() {
+                let Some(value) = *self[idx].value() else {
+                    value_list.push(None);
+                    continue;
+                };
+                let value = match last_value {
+                    Some((last_idx, last_value))
+                        if self.is_same_group(last_idx, idx) && last_value >= value =>
+                    {
+                        match last_value.checked_add(1) {
+                            Some(value) => value,
+                            None => return self.renumber_in_place(),
+                        }
+                    }
+                    _ => value,
+                };
+                value_list.push(Some(value));
+                last_value = Some((idx, value));
+            }
+            for (page, value) in self.iter_mut().zip(value_list) {
+                page.set_value(value);
+            }
+            Ok(())
+        }
+    }
+
+    /// Sort pages having values by a given comparator and assign sequential numbers beginning with 0.
+    /// Pages without values are placed at the end (or the beginning if unassigned pages come first), and directories are kept together if pages are grouped by directory.
+    /// With tiers, each tier is numbered from its base value, and numbering past the largest integer fails with the values left as they were.
+    /// 値を持つページを与えられた比較関数でソートし、0始まりの連番を割り当てる。
+    /// 値を持たないページは最後（未割り当てのページを先にする場合は最初）に回し、ディレクトリごとにまとめる場合はディレクトリを崩さない。
+    /// 階層がある場合は各階層を基準値から番号を振り、最大の整数を超える場合は値を元のままにして失敗する。
+    ///
+    /// ```no_run
+    /// # use order_in_yaml_frontmatter::page::PageList;
+    /// # fn reorder(page_list: &mut PageList) -> anyhow::Result<()> {
+    /// // Order pages by title.
+    /// page_list.reorder_by(|a, b| a.title().cmp(b.title()))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reorder_by<F: FnMut(&Page, &Page) -> Ordering>(&mut self, compare: F) -> Result<()> {
+        self.sort_pages(compare);
+        // New values are computed first, so that an overflow (only possible with a tier based near the largest integer)
+        // leaves the values as they were.
+        // 新しい値を先に計算し、オーバーフロー（最大の整数に近い基準値の階層でのみ起こりうる）しても値が元のままになるようにする。
+        let mut value_list = Vec::with_capacity(self.len());
+        let mut last_value: Option<i64> = None;
+        let mut current_tier_idx = None;
+        for idx in 0..self.len() {
+            if idx != 0 && !self.is_same_group(idx - 1, idx) {
+                last_value = None;
+                current_tier_idx = None;
+            }
+            let Some(value) = *self[idx].value() else {
+                value_list.push(None);
+                continue;
+            };
+            let mut new_value = match last_value {
+                Some(x) => x.checked_add(1).context(Self::OVERFLOW_MESSAGE)?,
+                None => 0,
+            };
+            if !self.tier_list.is_empty() {
+                // Each tier is numbered from its base value, or right after the previous tier if it has run over.
+                // 各階層は基準値から、前の階層があふれている場合はその直後から番号を振る。
+                let tier_idx = tier_idx(&self.tier_list, value);
+                if current_tier_idx != Some(tier_idx) {
+                    current_tier_idx = Some(tier_idx);
+                    new_value = cmp::max(new_value, self.tier_list[tier_idx].base);
+                }
+            }
+            value_list.push(Some(new_value));
+            last_value = Some(new_value);
+        }
+        for (page, value) in self.iter_mut().zip(value_list) {
+            page.set_value(value);
         }
         Ok(())
     }
+
+    /// Sort pages having values by a given comparator without changing values.
+    /// Pages are kept in the tiers of their values, and pinned pages at the top or the bottom of their tiers.
+    /// 値を変えずに、値を持つページを与えられた比較関数でソートする。
+    /// ページは値の階層の中に、固定されたページはその階層の先頭か末尾に留める。
+    fn sort_pages<F: FnMut(&Page, &Page) -> Ordering>(&mut self, mut compare: F) {
+        let tier_list = mem::take(&mut self.tier_list);
+        let group_by_dir = self.group_by_dir;
+        let unassigned_first = self.unassigned_first;
+        let slug_order = self.slug_order;
+        self.sort_by(|a, b| {
+            if group_by_dir {
+                let dir_ordering = a.path().parent().cmp(&b.path().parent());
+                if dir_ordering != Ordering::Equal {
+                    return dir_ordering;
+                }
+            }
+            let ordering = match (a.value(), b.value()) {
+                (Some(value_a), Some(value_b)) => {
+                    return tier_idx(&tier_list, *value_a)
+                        .cmp(&tier_idx(&tier_list, *value_b))
+                        .then_with(|| pin_rank(a.pin()).cmp(&pin_rank(b.pin())))
+                        .then_with(|| compare(a, b))
+                }
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) if slug_order => return a.slug().cmp(b.slug()),
+                (None, None) => Ordering::Equal,
+            };
+            if unassigned_first {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        self.tier_list = tier_list;
+    }
+
+    /// Renumber pages having values in the order they are listed now.
+    /// The sort is stable, so pages having values keep their order, and pages without values go to the end as usual.
+    /// 値を持つページに、今並んでいる順に番号を振り直す。
+    /// 安定ソートのため値を持つページの順序は変わらず、値を持たないページは通常どおり最後に回る。
+    pub fn renumber_in_place(&mut self) -> Result<()> {
+        self.reorder_by(|_, _| Ordering::Equal)
+    }
+
+    /// Sort pages having values by their slugs and renumber them.
+    /// 値を持つページをスラッグでソートし、番号を振り直す。
+    pub fn sort_by_slug(&mut self) -> Result<()> {
+        self.reorder_by(|a, b| a.slug().cmp(b.slug()))
+    }
+
+    /// Move a page to the end of the next tier, or the first one after the last, and renumber pages.
+    /// A page without a value is moved into the first tier.
+    /// ページを次の階層（最後の階層からは最初の階層）の末尾に動かし、番号を振り直す。
+    /// 値を持たないページは最初の階層に入れる。
+    pub fn next_tier(&mut self, idx: usize) -> Result<()> {
+        let Some(page) = self.get(idx) else {
+            bail!("failed to get {}-th element", idx);
+        };
+        if self.tier_list.is_empty() {
+            bail!("no tiers are defined");
+        }
+        if !page.is_in_scope() {
+            bail!("{} is out of scope", page.file_name());
+        }
+        if page.pin().is_some() {
+            bail!("{} is pinned", page.file_name());
+        }
+        let next_tier_idx = match *page.value() {
+            Some(value) => (tier_idx(&self.tier_list, value) + 1) % self.tier_list.len(),
+            None => 0,
+        };
+        let value = self
+            .tier_list
+            .get(next_tier_idx + 1)
+            .map_or(i64::MAX, |tier| tier.base - 1);
+        let path = page.path().clone();
+        self[idx].set_value(Some(value));
+        self.reorder_by(|a, b| {
+            a.value()
+                .cmp(b.value())
+                .then_with(|| (a.path() == &path).cmp(&(b.path() == &path)))
+        })
+    }
+
+    /// Name of the tier of the value of a page, if tiers are defined and the page has a value.
+    /// 階層が定義され、ページが値を持つ場合の、その値の階層の名前。
+    pub fn tier_name(&self, page: &Page) -> Option<&str> {
+        if self.tier_list.is_empty() {
+            return None;
+        }
+        let value = (*page.value())?;
+        Some(&self.tier_list[tier_idx(&self.tier_list, value)].name)
+    }
+
+    /// Renumber pages by a date in their FrontMatter, newest first, e.g. to order posts of a blog.
+    /// Pages having a date are included, and the others keep whether they have values and follow them in their order.
+    /// Returns the files in scope without a date.
+    /// FrontMatterの日付で、新しいものから順にページに番号を振り直す。例えばブログの記事を並べるため。
+    /// 日付を持つページは含め、それ以外のページは値の有無を保ったまま、今の順序でその後に続ける。
+    /// 範囲内で日付を持たないファイルを返す。
+    pub fn order_by_date(&mut self, field: &str) -> Result<Vec<PathBuf>> {
+        if self.key_type == KeyType::String {
+            bail!("values cannot be taken from dates when keys are strings");
+        }
+        let mut date_list = HashMap::new();
+        let mut undated_list = Vec::new();
+        for page in self.iter().filter(|page| page.is_in_scope()) {
+            let date = page.read_field(field)?;
+            match date.as_str().and_then(parse_date) {
+                Some(date) => {
+                    date_list.insert(page.path().clone(), date);
+                }
+                None => undated_list.push(page.path().clone()),
+            }
+        }
+        for page in self.iter_mut() {
+            if date_list.contains_key(page.path()) && page.value().is_none() {
+                page.set_value(Some(0));
+            }
+        }
+        self.reorder_by(
+            |a, b| match (date_list.get(a.path()), date_list.get(b.path())) {
+                (Some(date_a), Some(date_b)) => date_b.cmp(date_a),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => a.value().cmp(b.value()),
+            },
+        )?;
+        Ok(undated_list)
+    }
+
+    /// Permute values of the current key randomly among included pages of each group, except pinned pages.
+    /// 現在のキーの値を、固定されたページを除いて、各グループの値を持つページの間でランダムに並べ替える。
+    pub fn shuffle(&mut self) -> Result<()> {
+        let mut group_start = 0;
+        while group_start < self.len() {
+            let group_end = (group_start + 1..self.len())
+                .find(|&idx| !self.is_same_group(group_start, idx))
+                .unwrap_or(self.len());
+            let idx_list = (group_start..group_end)
+                .filter(|&idx| self[idx].value().is_some() && self[idx].pin().is_none())
+                .collect::<Vec<_>>();
+            let mut value_list = idx_list
+                .iter()
+                .map(|&idx| *self[idx].value())
+                .collect::<Vec<_>>();
+            fastrand::shuffle(&mut value_list);
+            for (idx, value) in idx_list.into_iter().zip(value_list) {
+                self[idx].set_value(value);
+            }
+            group_start = group_end;
+        }
+        self.sort_and_fix()
+    }
+
+    /// Whether two pages can be swapped: they are in the same group and neither is pinned.
+    /// 2つのページを入れ替えられるかどうか。同じグループにあり、どちらも固定されていない。
+    pub fn is_swappable(&self, idx_a: usize, idx_b: usize) -> bool {
+        self.is_same_group(idx_a, idx_b)
+            && self[idx_a].pin().is_none()
+            && self[idx_b].pin().is_none()
+    }
+
+    /// Whether two pages are numbered in the same sequence.
+    /// Always true unless pages are grouped by directory.
+    /// 2つのページが同じ連番に属するかどうか。ディレクトリごとにまとめない場合は常に真。
+    pub fn is_same_group(&self, idx_a: usize, idx_b: usize) -> bool {
+        !self.group_by_dir || self[idx_a].path().parent() == self[idx_b].path().parent()
+    }
+
+    /// The key to reorder.
+    /// 並べ替えの対象のキー。
+    pub fn key(&self) -> &str {
+        &self.key_list[self.key_idx]
+    }
+
+    fn set_key_idx(&mut self, key_idx: usize) {
+        self.key_idx = key_idx;
+        for page in self.iter_mut() {
+            page.key_idx = key_idx;
+        }
+    }
+
+    /// Switch to the next key and sort pages by it.
+    /// 次のキーに切り替え、そのキーでソートする。
+    pub fn cycle_key(&mut self) -> Result<()> {
+        self.set_key_idx((self.key_idx + 1) % self.key_list.len());
+        self.sort_and_fix()
+    }
+
+    /// Index of the page of a given file.
+    /// 与えられたファイルのページの位置。
+    pub fn position_of(&self, path: &Path) -> Option<usize> {
+        let canonical_path = fs::canonicalize(path).ok()?;
+        self.iter()
+            .position(|page| fs::canonicalize(page.path()).ok().as_ref() == Some(&canonical_path))
+    }
+
+    /// Remove the value if it exists, otherwise assign it.
+    /// Nothing happens when keys are strings, since no value can be made up for them, or when the page is pinned.
+    /// valueに値があれば外し、そうでなければ代入する
+    /// キーが文字列の場合は値を作れないため、またページが固定されている場合も、何もしない。
+    pub fn toggle_value(&mut self, idx: usize) -> Result<()> {
+        let Some(page) = self.get(idx) else {
+            bail!("failed to get {}-th element", idx);
+        };
+        if !page.is_in_scope() || page.pin().is_some() || self.key_type == KeyType::String {
+            return Ok(());
+        }
+        let unset = page.value().is_some();
+        let group_end = (idx + 1..self.len())
+            .find(|&next_idx| !self.is_same_group(idx, next_idx))
+            .unwrap_or(self.len());
+        // Every new value is computed before any of them is set, so that an overflow leaves the list as it was.
+        // 値を一つも設定する前に新しい値を全て計算し、オーバーフローしてもリストが元のままになるようにする。
+        let value = if unset {
+            None
+        } else {
+            let pre_value = (0..idx)
+                .rev()
+                .filter(|&pre_idx| self.is_same_group(pre_idx, idx))
+                .find_map(|pre_idx| *self[pre_idx].value());
+            Some(match pre_value {
+                Some(x) => x.checked_add(1).context(Self::OVERFLOW_MESSAGE)?,
+                None => 0,
+            })
+        };
+        let next_value_list = self[idx + 1..group_end]
+            .iter()
+            .map(|page| {
+                page.value()
+                    .map(|value| {
+                        if unset {
+                            value.checked_sub(1)
+                        } else {
+                            value.checked_add(1)
+                        }
+                        .context(Self::OVERFLOW_MESSAGE)
+                    })
+                    .transpose()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self[idx].set_value(value);
+        for (page, value) in self[idx + 1..group_end].iter_mut().zip(next_value_list) {
+            page.set_value(value);
+        }
+        Ok(())
+    }
+
+    /// Move a page to another position by swapping it with its neighbors one by one,
+    /// so that values stay ascending as with swaps.
+    /// 隣のページとの入れ替えを繰り返してページを別の位置に動かす。
+    /// 入れ替えと同じく値は昇順のまま保たれる。
+    pub fn move_to(&mut self, idx: usize, new_idx: usize) -> Result<()> {
+        if idx >= self.len() {
+            bail!("failed to get {}-th element", idx);
+        }
+        if new_idx >= self.len() {
+            bail!("position {} is out of the list", new_idx + 1);
+        }
+        if !self.is_same_group(idx, new_idx) {
+            bail!("failed to move a page to another directory");
+        }
+        // Check every page on the way first, so that a failure leaves the order untouched.
+        // 途中で失敗して並びが中途半端に変わらないよう、先に経路上の全ページを確かめる。
+        if let Some(page) = self[idx.min(new_idx)..=idx.max(new_idx)]
+            .iter()
+            .find(|page| page.pin().is_some())
+        {
+            bail!("{} is pinned", page.file_name());
+        }
+        for current_idx in idx..new_idx {
+            self.swap_with_value(current_idx, SwapDirection::Next)?;
+        }
+        for current_idx in (new_idx + 1..=idx).rev() {
+            self.swap_with_value(current_idx, SwapDirection::Prev)?;
+        }
+        Ok(())
+    }
+
+    /// Exchange the positions of two pages and their values, which need not be next to each other.
+    /// A page having a value cannot be exchanged with one without, since that would change which pages are included.
+    /// 隣り合っていなくてもよい2つのページの位置と値を入れ替える。
+    /// 値を持つページと持たないページは、含まれるページが変わってしまうため入れ替えられない。
+    pub fn swap_pages(&mut self, idx_a: usize, idx_b: usize) -> Result<()> {
+        if idx_a >= self.len() || idx_b >= self.len() {
+            bail!("failed to get {}-th element", idx_a.max(idx_b));
+        }
+        if !self.is_same_group(idx_a, idx_b) {
+            bail!("failed to swap pages in different directories");
+        }
+        if let Some(page) = [&self[idx_a], &self[idx_b]]
+            .into_iter()
+            .find(|page| page.pin().is_some())
+        {
+            bail!("{} is pinned", page.file_name());
+        }
+        match (*self[idx_a].value(), *self[idx_b].value()) {
+            (Some(value_a), Some(value_b)) => {
+                self[idx_a].set_value(Some(value_b));
+                self[idx_b].set_value(Some(value_a));
+            }
+            (None, None) => (),
+            _ => bail!("failed to swap a page having a value with one without"),
+        }
+        self.swap(idx_a, idx_b);
+        debug_assert!(self.is_ascending());
+        Ok(())
+    }
+
+    /// If both are not None, replace the value as well.
+    /// Swapping with a None page moves only the position, so that the relative order of pages
+    /// having values never changes and their values stay ascending.
+    /// 両方Noneでなければvalueも入れ替える。
+    /// Noneのページとの入れ替えでは位置だけを動かすため、値を持つページ同士の順序は変わらず、
+    /// 値は昇順のまま保たれる。
+    pub fn swap_with_value(&mut self, idx: usize, swap_direction: SwapDirection) -> Result<()> {
+        if idx >= self.len() {
+            bail!("failed to get {}-th element", idx);
+        } else {
+            match swap_direction {
+                SwapDirection::Prev => {
+                    if idx == 0 {
+                        bail!("failed to get previous one");
+                    }
+                }
+                SwapDirection::Next => {
+                    if idx == self.len() - 1 {
+                        bail!("failed to get next one");
+                    }
+                }
+            }
+        }
+        let idx_neighbor = match swap_direction {
+            SwapDirection::Prev => idx - 1,
+            SwapDirection::Next => idx + 1,
+        };
+        if !self.is_same_group(idx, idx_neighbor) {
+            bail!("failed to swap pages in different directories");
+        }
+        if let Some(page) = [&self[idx], &self[idx_neighbor]]
+            .into_iter()
+            .find(|page| page.pin().is_some())
+        {
+            bail!("{} is pinned", page.file_name());
+        }
+        if self.get(idx).unwrap().value().is_some()
+            && self.get(idx_neighbor).unwrap().value().is_some()
+        {
+            let value_neighbor = self.get(idx_neighbor).unwrap().value().unwrap();
+            let value = self.get(idx).unwrap().value().unwrap();
+            self.get_mut(idx).unwrap().set_value(Some(value_neighbor));
+            self.get_mut(idx_neighbor).unwrap().set_value(Some(value));
+        }
+        self.swap(idx, idx_neighbor);
+        debug_assert!(self.is_ascending());
+        Ok(())
+    }
+
+    /// Whether values are ascending in each group, ignoring None.
+    /// Noneを除いて、各グループで値が昇順になっているかどうか。
+    fn is_ascending(&self) -> bool {
+        let mut last_value: Option<(usize, i64)> = None;
+        for (idx, page) in self.iter().enumerate() {
+            if let Some(value) = *page.value() {
+                if let Some((last_idx, last_value)) = last_value {
+                    if self.is_same_group(last_idx, idx) && last_value >= value {
+                        return false;
+                    }
+                }
+                last_value = Some((idx, value));
+            }
+        }
+        true
+    }
+
+    /// Fix the values to be written.
+    /// FrontMatter is read again and updated when each page is written.
+    /// 書き込む値を確定させる。
+    /// FrontMatterは各ページを書き込むときに読み直して更新する。
+    pub fn substitute_value(&mut self) -> Result<()> {
+        if self.key_type == KeyType::String {
+            self.exchange_scalar()?;
+        }
+        Ok(())
+    }
+    /// How values are written to FrontMatter.
+    /// FrontMatterへの値の書き方。
+    fn write_option(&self) -> WriteOption {
+        WriteOption {
+            value_as_string: self.value_as_string,
+            keep_quoted: self.keep_quoted,
+            one_based: self.one_based,
+            clean_excluded: self.clean_excluded,
+            normalize: self.normalize,
+        }
+    }
+
+    /// Hand out the strings of each group to its pages in their new order.
+    /// The set of strings in a group never changes; only the pages holding them do.
+    /// 各グループの文字列を新しい順序でページに配り直す。
+    /// グループ内の文字列の集まりは変わらず、それを持つページだけが変わる。
+    fn exchange_scalar(&mut self) -> Result<()> {
+        for key_idx in 0..self.key_list.len() {
+            let mut group_start = 0;
+            while group_start < self.len() {
+                let group_end = (group_start + 1..self.len())
+                    .find(|&idx| !self.is_same_group(group_start, idx))
+                    .unwrap_or(self.len());
+                let mut scalar_list = self[group_start..group_end]
+                    .iter()
+                    .filter_map(|page| page.scalar_list[key_idx].clone())
+                    .collect::<Vec<_>>();
+                scalar_list.sort_by(|a, b| natural_cmp(&scalar_text(a), &scalar_text(b)));
+                for page in &mut self[group_start..group_end] {
+                    if let Some(rank) = page.value_list[key_idx] {
+                        let scalar = usize::try_from(rank)
+                            .ok()
+                            .and_then(|rank| scalar_list.get(rank))
+                            .with_context(|| {
+                                format!("no string is left for {}", page.path().display())
+                            })?;
+                        page.scalar_list[key_idx] = Some(scalar.clone());
+                    }
+                }
+                group_start = group_end;
+            }
+        }
+        Ok(())
+    }
+
+    /// Values of the current key as they are in files, one page per line as `value<TAB>path`
+    /// sorted by the values, with `none` for pages without values.
+    /// Pages the key does not apply to are omitted.
+    /// 現在のキーのファイル上の値を、値でソートして1行1ページの `値<TAB>パス` の形式で返す。
+    /// 値のないページは `none` とする。キーが適用されないページは含めない。
+    pub fn current_value_summary(&self) -> String {
+        let mut page_list = self
+            .iter()
+            .filter(|page| page.is_in_scope())
+            .collect::<Vec<_>>();
+        page_list.sort_by(|a, b| match (a.value_old(), b.value_old()) {
+            (Some(value_a), Some(value_b)) => value_a.cmp(value_b).then(a.path().cmp(b.path())),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => a.path().cmp(b.path()),
+        });
+        let mut summary = String::new();
+        for page in page_list {
+            let value = match page.value_old() {
+                Some(value) => page.scalar_text().unwrap_or_else(|| value.to_string()),
+                None => "none".to_owned(),
+            };
+            writeln!(summary, "{}\t{}", value, page.path().display()).unwrap();
+        }
+        summary
+    }
+
+    /// Effect of a save as JSON: counts of changed and unchanged files,
+    /// and the old and new values of each changed key of each file.
+    /// Nothing is counted as changed when the changes are not saved.
+    /// 保存による変更をJSONで返す。変更されたファイルとされなかったファイルの数、
+    /// および各ファイルで変更されたキーの新旧の値を含む。
+    /// 保存されなかった場合は何も変更されていないとする。
+    pub fn change_summary(&self, saved: bool) -> serde_json::Value {
+        let change_list = if saved {
+            self.pending_changes()
+        } else {
+            Vec::new()
+        };
+        let changed_file_count = change_list
+            .iter()
+            .map(|change| change.path)
+            .collect::<HashSet<_>>()
+            .len();
+        json!({
+            "saved": saved,
+            "changed_files": changed_file_count,
+            "unchanged_files": self.len() - changed_file_count,
+            "changes": change_list
+                .iter()
+                .map(|change| json!({
+                    "path": change.path.display().to_string(),
+                    "key": change.key,
+                    "old": change.old_value,
+                    "new": change.new_value,
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    /// Values of every key differing from the ones in files, in the order of pages and then keys.
+    /// This is what saving would change, as shown before saving and in the JSON summary.
+    /// Values are given as JSON: null when a page has none, the string when keys are strings, otherwise the integer.
+    /// 全てのキーの、ファイルの値と異なる値。ページ、キーの順に並ぶ。
+    /// 保存によって変わるもので、保存の前の確認やJSONの要約に示す。
+    /// 値はJSONで与える。値がなければnull、キーが文字列の場合はその文字列、そうでなければ整数。
+    ///
+    /// ```no_run
+    /// # use order_in_yaml_frontmatter::page::PageList;
+    /// # fn print(page_list: &PageList) {
+    /// for change in page_list.pending_changes() {
+    ///     println!("{}: {} {} -> {}", change.path.display(), change.key, change.old_value, change.new_value);
+    /// }
+    /// # }
+    /// ```
+    pub fn pending_changes(&self) -> Vec<PageChange<'_>> {
+        let mut change_list = Vec::new();
+        for page in self.iter() {
+            for (key_idx, key) in self.key_list.iter().enumerate() {
+                if !page.in_scope_list[key_idx] {
+                    continue;
+                }
+                let old = Page::json_value(
+                    page.value_old_list[key_idx],
+                    page.scalar_old_list[key_idx].as_ref(),
+                );
+                let new =
+                    Page::json_value(page.value_list[key_idx], page.scalar_list[key_idx].as_ref());
+                if old != new {
+                    change_list.push(PageChange {
+                        path: page.path(),
+                        key,
+                        old_value: old,
+                        new_value: new,
+                    });
+                }
+            }
+        }
+        change_list
+    }
+
+    /// Number of files that saving would write.
+    /// 保存によって書き込まれるファイルの数。
+    pub fn rewrite_count(&self) -> usize {
+        self.iter()
+            .filter(|page| page.is_rewritten(self.clean_excluded, self.normalize))
+            .count()
+    }
+
+    /// How loading renumbered the current key: the number of pages and the ranges of their values before and after
+    /// as they are written in files (from 1 with `--one-based`), or None if no value is changed.
+    /// 読み込みで現在のキーの番号がどう振り直されたか。ページ数と、ファイルに書かれる形での前後の値の範囲
+    /// （`--one-based`では1から）。値が変わっていなければNone。
+    pub fn load_renumber_summary(&self) -> Option<String> {
+        let key_idx = self.key_idx;
+        let renumbered_list = self
+            .iter()
+            .filter(|page| page.value_load_list[key_idx] != page.value_old_list[key_idx])
+            .collect::<Vec<_>>();
+        if self.key_type == KeyType::String || renumbered_list.is_empty() {
+            return None;
+        }
+        let shift = i64::from(self.one_based);
+        let range = |value_list: Vec<i64>| match (value_list.iter().min(), value_list.iter().max())
+        {
+            (Some(min), Some(max)) if min == max => min.to_string(),
+            (Some(min), Some(max)) => format!("{}..{}", min, max),
+            _ => "none".to_owned(),
+        };
+        let (page_noun, value_noun, verb) = match renumbered_list.len() {
+            1 => ("page", "value", "becomes"),
+            _ => ("pages", "values", "become"),
+        };
+        Some(format!(
+            "loading renumbers {} of {} {}: {} {} {} {}",
+            self.key(),
+            renumbered_list.len(),
+            page_noun,
+            value_noun,
+            range(
+                renumbered_list
+                    .iter()
+                    .filter_map(|page| page.value_old_list[key_idx])
+                    .map(|value| value + shift)
+                    .collect()
+            ),
+            verb,
+            range(
+                renumbered_list
+                    .iter()
+                    .filter_map(|page| page.value_load_list[key_idx])
+                    .map(|value| value + shift)
+                    .collect()
+            ),
+        ))
+    }
+
+    /// Pages whose values of the current key in files are shared with other pages of the same group,
+    /// one page per line as `value<TAB>path` in the current order (pages sharing a value are in the order found).
+    /// Strings are never renumbered, so nothing is returned when keys are strings.
+    /// 現在のキーのファイル上の値が同じグループの他のページと重複しているページを、
+    /// 現在の順序で1行1ページの `値<TAB>パス` の形式で返す（値が重複するページは見つかった順に並ぶ）。
+    /// 文字列は番号を振り直さないため、キーが文字列の場合は何も返さない。
+    pub fn duplicate_summary(&self) -> String {
+        let mut summary = String::new();
+        if self.key_type == KeyType::String {
+            return summary;
+        }
+        for (idx, page) in self.iter().enumerate() {
+            let Some(value) = page.value_old() else {
+                continue;
+            };
+            let duplicated = self.iter().enumerate().any(|(other_idx, other)| {
+                other_idx != idx
+                    && other.value_old() == page.value_old()
+                    && other.is_in_scope()
+                    && self.is_same_group(idx, other_idx)
+            });
+            if page.is_in_scope() && duplicated {
+                writeln!(summary, "{}\t{}", value, page.path().display()).unwrap();
+            }
+        }
+        summary
+    }
+
+    /// Pages whose values of the current key in files are below 1 with `--one-based`,
+    /// one page per line as `value<TAB>path` in the current order.
+    /// Nothing is returned without `--one-based` or when keys are strings.
+    /// `--one-based`の場合に、現在のキーのファイル上の値が1未満のページを、
+    /// 現在の順序で1行1ページの `値<TAB>パス` の形式で返す。
+    /// `--one-based`でない場合とキーが文字列の場合は何も返さない。
+    pub fn below_one_summary(&self) -> String {
+        let mut summary = String::new();
+        if !self.one_based || self.key_type == KeyType::String {
+            return summary;
+        }
+        for page in self.iter().filter(|page| page.is_in_scope()) {
+            if let Some(value) = page.value_old().filter(|value| *value < 0) {
+                writeln!(summary, "{}\t{}", value + 1, page.path().display()).unwrap();
+            }
+        }
+        summary
+    }
+
+    /// The order for the current key, one page per line as `value<TAB>path`.
+    /// Excluded pages follow a `--` divider in path order with `-` in place of the value.
+    /// Pages the key does not apply to are omitted.
+    /// 現在のキーでの順序を、1行1ページの `値<TAB>パス` の形式で返す。
+    /// 除外されたページは区切り `--` の後にパス順で、値の代わりに `-` を付けて並べる。
+    /// キーが適用されないページは含めない。
+    pub fn order_summary(&self) -> String {
+        let mut summary = String::new();
+        let mut excluded_path_list = Vec::new();
+        for page in self.iter().filter(|page| page.is_in_scope()) {
+            match page.value() {
+                Some(value) => writeln!(
+                    summary,
+                    "{}\t{}",
+                    page.scalar_text().unwrap_or_else(|| value.to_string()),
+                    page.path().display()
+                )
+                .unwrap(),
+                None => excluded_path_list.push(page.path()),
+            }
+        }
+        if !excluded_path_list.is_empty() {
+            excluded_path_list.sort();
+            summary.push_str("--\n");
+            for path in excluded_path_list {
+                writeln!(summary, "-\t{}", path.display()).unwrap();
+            }
+        }
+        summary
+    }
+
+    /// A unified diff of the changes saving would make, to be applied with `patch -p0`.
+    /// Paths are relative to the current directory if they are under it.
+    /// 保存による変更の統一diff形式。`patch -p0`で適用できる。
+    /// パスはカレントディレクトリの下にあればそこからの相対パスにする。
+    pub fn patch(&self) -> Result<String> {
+        let current_dir = std::env::current_dir()?;
+        let mut patch = String::new();
+        for page in self.iter() {
+            if let Some((content, new_content)) =
+                page.new_content(&self.key_list, &self.write_option())?
+            {
+                let path = page.path();
+                let path = path
+                    .strip_prefix(&current_dir)
+                    .unwrap_or(path)
+                    .display()
+                    .to_string();
+                let diff = similar::TextDiff::from_lines(&content, &new_content);
+                write!(patch, "{}", diff.unified_diff().header(&path, &path))?;
+            }
+        }
+        Ok(patch)
+    }
+
+    /// Read a page again from its file, e.g. after it is edited outside the TUI.
+    /// The values, their scopes and the pin of the page in the session are kept, so the order survives,
+    /// and values differing from the file are written on save.
+    /// ページをファイルから読み直す。TUIの外で編集した後などに使う。
+    /// セッションでのページの値、その適用範囲、固定は保つため順序はそのまま残り、ファイルと異なる値は保存時に書き込まれる。
+    pub fn reload_page(&mut self, idx: usize) -> Result<()> {
+        let old_page = &self[idx];
+        let mut page = Page::try_new(
+            old_page.path(),
+            &self.key_list,
+            &self.category_list,
+            self.filter_tag.as_deref(),
+            self.skip_draft
+                .as_ref()
+                .map(|(draft_key, draft_mode)| (draft_key.as_str(), *draft_mode)),
+            self.key_type,
+            self.read_option(old_page.path()),
+        )?;
+        if self.one_based {
+            page.shift_from_one_based();
+        }
+        if self.key_type == KeyType::Integer {
+            // Keys which do not apply to the page are never written, so their old values do not matter.
+            // ページに適用されないキーは書き込まれないため、その古い値は問題にならない。
+            page.value_old_list = page
+                .value_old_list
+                .iter()
+                .zip(&old_page.in_scope_list)
+                .map(|(value, in_scope)| value.filter(|_| *in_scope))
+                .collect();
+        } else {
+            page.value_old_list = old_page.value_old_list.clone();
+            page.scalar_old_list = old_page.scalar_old_list.clone();
+        }
+        page.value_list = old_page.value_list.clone();
+        page.value_load_list = old_page.value_load_list.clone();
+        page.scalar_list = old_page.scalar_list.clone();
+        page.in_scope_list = old_page.in_scope_list.clone();
+        page.key_idx = old_page.key_idx;
+        page.pin = old_page.pin;
+        page.relative_path = old_page.relative_path.clone();
+        page.symlinked = old_page.symlinked;
+        page.outside_target = old_page.outside_target;
+        page.note = old_page.note.clone();
+        self[idx] = page;
+        Ok(())
+    }
+
+    /// Write the values to the files, or to copies of every file under the output directory if it is given.
+    /// A file is written in place only if its content changes, so unchanged files keep their modification times.
+    /// 値をファイルに書き込む。出力ディレクトリが与えられた場合は、その下に全てのファイルのコピーを書き込む。
+    /// その場で書き込むのは内容が変わるファイルだけなので、変わらないファイルの更新日時は保たれる。
+    pub fn overwrite_frontmatter(&mut self) -> Result<()> {
+        let write_option = self.write_option();
+        for page in self.iter() {
+            match &self.output_dir {
+                Some(output_dir) => page.write_copy(&self.key_list, &write_option, output_dir)?,
+                None => page.overwrite_frontmatter(&self.key_list, &write_option)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{config, entry, order, write};
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[cfg(unix)]
+    #[test]
+    fn file_reached_twice_is_listed_once() {
+        let dir = TempDir::new().unwrap();
+        let path = write(&dir, "a.md", "---\nweight: 0\n---\n");
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        std::os::unix::fs::symlink(&path, dir.path().join("link.md")).unwrap();
+        std::os::unix::fs::symlink(&path, dir.path().join("sub/link.md")).unwrap();
+        // A link back to the target directory makes a loop, which is walked only once.
+        // 対象ディレクトリへ戻るリンクはループになるが、一度だけたどる。
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("sub/up")).unwrap();
+        // Entries are read in the order of their paths, so the file itself comes first.
+        // エントリはパスの順に読むため、ファイル自身が先に来る。
+        let page_list = PageList::try_new(&config(dir.path(), &["--recursive"])).unwrap();
+        assert_eq!(order(&page_list), [entry("a.md", Some(0))]);
+        // A file given as the target is listed with its siblings, but only once.
+        // 対象として与えたファイルは兄弟とともに一覧に載るが、一度だけである。
+        let page_list = PageList::try_new(&config(&path, &[])).unwrap();
+        assert_eq!(order(&page_list), [entry("a.md", Some(0))]);
+    }
+
+    /// Save a single page with `weight: 5`, which is renumbered to 0,
+    /// and check that nothing else in the file changes.
+    /// `weight: 5`のページを1つ保存して0に振り直し、ファイルの他の部分が変わらないことを確かめる。
+    fn assert_round_trip(file_name: &str, content: &str) {
+        let dir = TempDir::new().unwrap();
+        let path = write(&dir, file_name, content);
+        let mut page_list = PageList::try_new(&config(dir.path(), &[])).unwrap();
+        page_list.substitute_value().unwrap();
+        page_list.overwrite_frontmatter().unwrap();
+        assert_eq!(
+            fs::read_to_string(path).unwrap(),
+            content.replace("weight: 5", "weight: 0")
+        );
+    }
+
+    #[test]
+    fn round_trip_keeps_dots_closing_line() {
+        assert_round_trip("a.md", "---\ntitle: A\nweight: 5\n...\nBody\n");
+    }
+
+    #[test]
+    fn round_trip_keeps_closing_line_right_after_frontmatter() {
+        assert_round_trip("a.md", "---\ntitle: A\nweight: 5\n---\nBody\n---\n");
+        assert_round_trip("b.md", "---\ntitle: B\nweight: 5\n\n---\nBody\n");
+    }
+
+    #[test]
+    fn round_trip_keeps_mdx_body_verbatim() {
+        assert_round_trip(
+            "a.mdx",
+            "---\ntitle: A\nweight: 5\n---\n\nimport { Tabs } from 'nextra/components'\n\n<Note>\n---\n</Note>\n\n```yaml\n---\nweight: 1\n---\n```\n\n<Tabs items={['a', 'b']}>\n  <Tabs.Tab>---</Tabs.Tab>\n</Tabs>\n",
+        );
+    }
+
+    #[test]
+    fn round_trip_keeps_leading_comment() {
+        assert_round_trip(
+            "a.md",
+            "<!--\n  Generated by a script.\n-->\n\n---\ntitle: A\nweight: 5\n---\nBody\n",
+        );
+    }
+
+    #[test]
+    fn round_trip_keeps_crlf_line_endings() {
+        assert_round_trip("a.md", "---\r\ntitle: A\r\nweight: 5\r\n---\r\nBody\r\n");
+        assert_round_trip(
+            "b.md",
+            "\u{feff}---\r\ntitle: BOM\r\nweight: 5\r\n\r\n---\r\nBody\r\n",
+        );
+    }
+
+    #[test]
+    fn swap_with_value_crosses_page_without_value() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.md", "---\nweight: 0\n---\n");
+        write(&dir, "b.md", "---\nweight: 1\n---\n");
+        write(&dir, "c.md", "---\ntitle: C\n---\n");
+        let mut page_list = PageList::try_new(&config(dir.path(), &[])).unwrap();
+        page_list.swap_with_value(1, SwapDirection::Next).unwrap();
+        assert_eq!(
+            order(&page_list),
+            [
+                entry("a.md", Some(0)),
+                entry("c.md", None),
+                entry("b.md", Some(1))
+            ]
+        );
+        page_list.swap_with_value(2, SwapDirection::Prev).unwrap();
+        assert_eq!(
+            order(&page_list),
+            [
+                entry("a.md", Some(0)),
+                entry("b.md", Some(1)),
+                entry("c.md", None)
+            ]
+        );
+    }
+
+    #[test]
+    fn swap_with_value_stays_in_directory() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("x")).unwrap();
+        fs::create_dir(dir.path().join("y")).unwrap();
+        write(&dir, "x/a.md", "---\nweight: 0\n---\n");
+        write(&dir, "x/c.md", "---\ntitle: C\n---\n");
+        write(&dir, "y/d.md", "---\nweight: 0\n---\n");
+        let mut page_list =
+            PageList::try_new(&config(dir.path(), &["--recursive", "--group-by-dir"])).unwrap();
+        page_list.swap_with_value(0, SwapDirection::Next).unwrap();
+        assert_eq!(
+            order(&page_list),
+            [
+                entry("c.md", None),
+                entry("a.md", Some(0)),
+                entry("d.md", Some(0))
+            ]
+        );
+        assert!(page_list.swap_with_value(1, SwapDirection::Next).is_err());
+        page_list.swap_with_value(1, SwapDirection::Prev).unwrap();
+        assert_eq!(
+            order(&page_list),
+            [
+                entry("a.md", Some(0)),
+                entry("c.md", None),
+                entry("d.md", Some(0))
+            ]
+        );
+    }
+
+    #[test]
+    fn natural_cmp_compares_digits_as_numbers() {
+        assert_eq!(natural_cmp("1.9.0", "1.10.0"), Ordering::Less);
+        assert_eq!(natural_cmp("1.10.0", "1.9.0"), Ordering::Greater);
+        assert_eq!(natural_cmp("v02", "v10"), Ordering::Less);
+        assert_eq!(natural_cmp("chapter2", "chapter10"), Ordering::Less);
+        assert_eq!(natural_cmp("a9", "b1"), Ordering::Less);
+        assert_eq!(natural_cmp("a", "a1"), Ordering::Less);
+        assert_eq!(natural_cmp("1.2", "1.2"), Ordering::Equal);
+        // Leading zeros do not change the number, but still keep the order total.
+        // 先頭のゼロは数を変えないが、順序が全順序になるように区別する。
+        assert_eq!(natural_cmp("v01", "v1"), Ordering::Less);
+        assert_eq!(natural_cmp("v1", "v01"), Ordering::Greater);
+    }
+
+    #[test]
+    fn swap_exchanges_strings_without_renumbering() {
+        let dir = TempDir::new().unwrap();
+        let path_a = write(&dir, "a.md", "---\nweight: 1.10.0\n---\n");
+        let path_b = write(&dir, "b.md", "---\nweight: 1.9.0\n---\n");
+        let mut page_list =
+            PageList::try_new(&config(dir.path(), &["--key-type", "string"])).unwrap();
+        assert_eq!(
+            order(&page_list),
+            [entry("b.md", Some(0)), entry("a.md", Some(1))]
+        );
+        page_list.swap_with_value(0, SwapDirection::Next).unwrap();
+        page_list.substitute_value().unwrap();
+        page_list.overwrite_frontmatter().unwrap();
+        assert_eq!(
+            fs::read_to_string(path_a).unwrap(),
+            "---\nweight: 1.9.0\n---\n"
+        );
+        assert_eq!(
+            fs::read_to_string(path_b).unwrap(),
+            "---\nweight: 1.10.0\n---\n"
+        );
+    }
+
+    #[test]
+    fn toggle_value_reports_overflow_and_keeps_list() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.md", "---\nweight: 0\n---\n");
+        write(&dir, "b.md", "---\nweight: 1\n---\n");
+        write(&dir, "c.md", "---\ntitle: C\n---\n");
+        let mut page_list = PageList::try_new(&config(dir.path(), &[])).unwrap();
+        page_list[0].set_value(Some(i64::MAX - 1));
+        page_list[1].set_value(Some(i64::MAX));
+        let before = order(&page_list);
+        assert_eq!(
+            before,
+            [
+                entry("a.md", Some(i64::MAX - 1)),
+                entry("b.md", Some(i64::MAX)),
+                entry("c.md", None),
+            ]
+        );
+        let err = page_list.toggle_value(2).unwrap_err();
+        assert_eq!(err.to_string(), PageList::OVERFLOW_MESSAGE);
+        assert_eq!(order(&page_list), before);
+    }
+
+    #[test]
+    fn huge_values_are_renumbered_densely() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.md", "---\nweight: 9223372036854775807\n---\n");
+        write(&dir, "b.md", "---\nweight: 9223372036854775807\n---\n");
+        write(&dir, "c.md", "---\nweight: 3\n---\n");
+        let expected = [
+            entry("c.md", Some(0)),
+            entry("a.md", Some(1)),
+            entry("b.md", Some(2)),
+        ];
+        let page_list = PageList::try_new(&config(dir.path(), &[])).unwrap();
+        assert_eq!(order(&page_list), expected);
+        // Raising the shared value would overflow, so pages are renumbered from 0 instead.
+        // 重複した値を上げるとオーバーフローするため、代わりに0から番号を振り直す。
+        let page_list = PageList::try_new(&config(dir.path(), &["--no-renumber-on-load"])).unwrap();
+        assert_eq!(order(&page_list), expected);
+    }
+
+    #[test]
+    fn split_path_text_splits_at_last_separator() {
+        let windows = &['\\', '/'];
+        assert_eq!(
+            split_path_text(r"C:\site\content\a.md", windows),
+            (r"C:\site\content", "a.md")
+        );
+        assert_eq!(split_path_text(r"C:\a.md", windows), (r"C:\", "a.md"));
+        assert_eq!(
+            split_path_text("C:/site/a.md", windows),
+            ("C:/site", "a.md")
+        );
+        assert_eq!(
+            split_path_text(r"\\server\share\a.md", windows),
+            (r"\\server\share", "a.md")
+        );
+        assert_eq!(split_path_text("/site/a.md", &['/']), ("/site", "a.md"));
+        assert_eq!(split_path_text("/a.md", &['/']), ("/", "a.md"));
+        assert_eq!(split_path_text("a.md", &['/']), ("", "a.md"));
+        // A backslash is a character of a file name on Unix.
+        // Unixではバックスラッシュはファイル名の文字である。
+        assert_eq!(split_path_text(r"site/a\b.md", &['/']), ("site", r"a\b.md"));
+    }
+
+    #[test]
+    fn strip_dir_text_keeps_rest_below_base() {
+        let windows = &['\\', '/'];
+        assert_eq!(
+            strip_dir_text(r"C:\site\content\posts", r"C:\site", windows),
+            Some(r"content\posts")
+        );
+        assert_eq!(strip_dir_text(r"C:\site", r"C:\site\", windows), Some(""));
+        assert_eq!(
+            strip_dir_text(r"C:\site\a", r"C:\", windows),
+            Some(r"site\a")
+        );
+        assert_eq!(strip_dir_text(r"C:\sites", r"C:\site", windows), None);
+        assert_eq!(strip_dir_text(r"D:\site", r"C:\site", windows), None);
+        assert_eq!(
+            strip_dir_text("/home/me/site", "/home/me", &['/']),
+            Some("site")
+        );
+        assert_eq!(strip_dir_text("/site", "/", &['/']), Some("site"));
+        assert_eq!(strip_dir_text("/home/meme", "/home/me", &['/']), None);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn file_and_dir_names_split_drive_letter_paths() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.md", "---\nweight: 0\n---\n");
+        let mut page_list = PageList::try_new(&config(dir.path(), &[])).unwrap();
+        let page = &mut page_list[0];
+        page.set_path(PathBuf::from(r"C:\site\content\a.md"));
+        assert_eq!(page.file_name(), "a.md");
+        assert_eq!(page.dir_name(), r"C:\site\content");
+        page.set_path(PathBuf::from(r"C:\a.md"));
+        assert_eq!(page.file_name(), "a.md");
+        assert_eq!(page.dir_name(), r"C:\");
+    }
+
+    #[test]
+    fn overwrite_frontmatter_replaces_existing_file() {
+        let dir = TempDir::new().unwrap();
+        let path = write(&dir, "a.md", "---\ntitle: A\nweight: 5\n---\nBody\n");
+        let mut page_list = PageList::try_new(&config(dir.path(), &[])).unwrap();
+        page_list.substitute_value().unwrap();
+        page_list.overwrite_frontmatter().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "---\ntitle: A\nweight: 0\n---\nBody\n"
+        );
+        // The temporary file is moved over the original, so nothing is left beside it.
+        // 一時ファイルは元のファイルの上に移されるため、隣には何も残らない。
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn save_keeps_modification_time_of_unchanged_file() {
+        let dir = TempDir::new().unwrap();
+        let unchanged = write(&dir, "a.md", "---\ntitle: A\nweight: 0\n---\n");
+        let changed = write(&dir, "b.md", "---\ntitle: B\nweight: 5\n---\n");
+        let old_time = SystemTime::now() - Duration::from_secs(3600);
+        for path in [&unchanged, &changed] {
+            fs::File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(old_time)
+                .unwrap();
+        }
+        let mut page_list = PageList::try_new(&config(dir.path(), &[])).unwrap();
+        page_list.substitute_value().unwrap();
+        page_list.overwrite_frontmatter().unwrap();
+        assert_eq!(
+            fs::metadata(&unchanged).unwrap().modified().unwrap(),
+            old_time
+        );
+        assert_ne!(
+            fs::metadata(&changed).unwrap().modified().unwrap(),
+            old_time
+        );
+        assert_eq!(
+            fs::read_to_string(&changed).unwrap(),
+            "---\ntitle: B\nweight: 1\n---\n"
+        );
+    }
+
+    #[test]
+    fn missing_target_fails_unless_created() {
+        let dir = TempDir::new().unwrap();
+        let target = dir.path().join("content");
+        let err = PageList::try_new(&config(&target, &[])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("target directory does not exist: {}", target.display())
+        );
+        assert!(!target.exists());
+        let page_list = PageList::try_new(&config(&target, &["--create-target"])).unwrap();
+        assert!(page_list.is_empty());
+        assert!(target.is_dir());
+    }
+
+    #[test]
+    fn ext_format_reads_and_saves_toml_frontmatter() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.md", "---\nweight: 5\n---\n");
+        let path = write(
+            &dir,
+            "b.html",
+            "+++\ntitle = \"B\"\nweight = 9 # order\n\n[extra]\nweight = 7\n+++\nBody\n",
+        );
+        // FrontMatter in the other format is skipped as if there were none.
+        // もう一方の形式のFrontMatterは、ないものとして飛ばす。
+        write(&dir, "c.html", "---\nweight: 1\n---\n");
+        write(&dir, "d.md", "+++\nweight = 1\n+++\n");
+        let mut page_list =
+            PageList::try_new(&config(dir.path(), &["--ext-format", "html=toml"])).unwrap();
+        assert_eq!(
+            order(&page_list),
+            [entry("a.md", Some(0)), entry("b.html", Some(1))]
+        );
+        assert_eq!(page_list[1].format(), &FrontMatterFormat::Toml);
+        assert_eq!(page_list[1].title().as_deref(), Some("B"));
+        page_list.substitute_value().unwrap();
+        page_list.overwrite_frontmatter().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "+++\ntitle = \"B\"\nweight = 1 # order\n\n[extra]\nweight = 7\n+++\nBody\n"
+        );
+    }
+
+    #[test]
+    fn format_auto_reads_each_file_in_its_own_format() {
+        let dir = TempDir::new().unwrap();
+        let yaml_path = write(&dir, "a.md", "---\nweight: 3 # order\n---\n");
+        let toml_path = write(&dir, "b.md", "+++\nweight = 5 # order\n+++\n");
+        // A format given to the extension is used instead of the opening line.
+        // 拡張子に与えた形式を開始行の代わりに使う。
+        write(&dir, "c.html", "+++\nweight = 1\n+++\n");
+        let mut page_list = PageList::try_new(&config(
+            dir.path(),
+            &["--format", "auto", "--ext-format", "html=yaml"],
+        ))
+        .unwrap();
+        assert_eq!(
+            order(&page_list),
+            [entry("a.md", Some(0)), entry("b.md", Some(1))]
+        );
+        assert_eq!(
+            page_list
+                .iter()
+                .map(|page| *page.format())
+                .collect::<Vec<_>>(),
+            [FrontMatterFormat::Yaml, FrontMatterFormat::Toml]
+        );
+        page_list.swap_pages(0, 1).unwrap();
+        page_list.substitute_value().unwrap();
+        page_list.overwrite_frontmatter().unwrap();
+        assert_eq!(
+            fs::read_to_string(&yaml_path).unwrap(),
+            "---\nweight: 1 # order\n---\n"
+        );
+        assert_eq!(
+            fs::read_to_string(&toml_path).unwrap(),
+            "+++\nweight = 0 # order\n+++\n"
+        );
+    }
+
+    #[test]
+    fn edit_toml_line_follows_tables() {
+        let value = Yaml::Integer(0);
+        assert_eq!(
+            edit_toml_line(
+                "weight = 5\n[menu.main]\nweight = 5\n",
+                "menu.main.weight",
+                Some(&value)
+            )
+            .unwrap(),
+            "weight = 5\n[menu.main]\nweight = 0\n"
+        );
+        assert_eq!(
+            edit_toml_line("menu . 'main'.weight=5\n", "menu.main.weight", Some(&value)).unwrap(),
+            "menu . 'main'.weight=0\n"
+        );
+        // A missing key is added to the deepest table it belongs to.
+        // ないキーは、属する最も深いテーブルに追加する。
+        assert_eq!(
+            edit_toml_line(
+                "title = \"A\"\n\n[menu]\nname = \"a\"\n\n[[list]]\nx = 1\n",
+                "menu.main.weight",
+                Some(&value)
+            )
+            .unwrap(),
+            "title = \"A\"\n\n[menu]\nname = \"a\"\nmain.weight = 0\n\n[[list]]\nx = 1\n"
+        );
+        assert_eq!(
+            edit_toml_line("title = \"A\"\n", "weight", Some(&value)).unwrap(),
+            "title = \"A\"\nweight = 0\n"
+        );
+        // Lines in a multi-line string or array are not taken for keys.
+        // 複数行の文字列や配列の中の行はキーとみなさない。
+        assert_eq!(
+            edit_toml_line(
+                "note = \"\"\"\nweight = 5\n\"\"\"\nlist = [\n  \"]\",\n]\nweight = 5\n",
+                "weight",
+                Some(&value)
+            )
+            .unwrap(),
+            "note = \"\"\"\nweight = 5\n\"\"\"\nlist = [\n  \"]\",\n]\nweight = 0\n"
+        );
+        assert_eq!(
+            edit_toml_line("weight = \"\"\"\n5\"\"\"\n", "weight", Some(&value)),
+            None
+        );
+    }
+
+    #[test]
+    fn move_to_across_pinned_page_keeps_order() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.md", "---\nweight: 1\n---\n");
+        write(&dir, "b.md", "---\nweight: 2\n---\n");
+        write(&dir, "c.md", "---\nweight: 3\n---\n");
+        write(&dir, "d.md", "---\nweight: 4\npinned: last\n---\n");
+        write(&dir, "p.md", "---\nweight: 0\npinned: first\n---\n");
+        let mut page_list = PageList::try_new(&config(dir.path(), &[])).unwrap();
+        let before = order(&page_list);
+        assert_eq!(before[0].0, "p.md");
+        assert!(page_list.move_to(3, 0).is_err());
+        assert_eq!(order(&page_list), before);
+        assert!(page_list.move_to(1, 4).is_err());
+        assert_eq!(order(&page_list), before);
+        page_list.move_to(3, 1).unwrap();
+        assert_eq!(
+            order(&page_list)
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>(),
+            ["p.md", "c.md", "a.md", "b.md", "d.md"]
+        );
+    }
+
+    #[test]
+    fn save_edits_only_changed_lines_unless_normalized() {
+        let content = "---\ntitle: A # shown in the menu\ntags: [x, y]\nweight: 5\n---\nBody\n";
+        let dir = TempDir::new().unwrap();
+        let path = write(&dir, "a.md", content);
+        let mut page_list = PageList::try_new(&config(dir.path(), &[])).unwrap();
+        page_list.substitute_value().unwrap();
+        page_list.overwrite_frontmatter().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            content.replace("weight: 5", "weight: 0")
+        );
+        let mut page_list = PageList::try_new(&config(dir.path(), &["--normalize"])).unwrap();
+        page_list.substitute_value().unwrap();
+        page_list.overwrite_frontmatter().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "---\ntitle: A\ntags:\n  - x\n  - y\nweight: 0\n---\nBody\n"
+        );
+    }
+
+    #[test]
+    fn clean_excluded_removes_commented_key_only() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.md", "---\nweight: 0\n---\n");
+        let path = write(
+            &dir,
+            "b.md",
+            "---\ntitle: B # kept\n# weight: 3\ntags: [x]\n---\n",
+        );
+        let mut page_list = PageList::try_new(&config(dir.path(), &["--clean-excluded"])).unwrap();
+        page_list.substitute_value().unwrap();
+        page_list.overwrite_frontmatter().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "---\ntitle: B # kept\ntags: [x]\n---\n"
+        );
+    }
+
+    #[test]
+    fn edit_yaml_line_keeps_quoted_key_and_comment() {
+        let value = Yaml::Integer(0);
+        assert_eq!(
+            edit_yaml_line("\"weight\": 5 # order\n", "weight", Some(&value)).unwrap(),
+            "\"weight\": 0 # order\n"
+        );
+        assert_eq!(
+            edit_yaml_line("menu:\n  'weight': 5\n", "menu.weight", Some(&value)).unwrap(),
+            "menu:\n  'weight': 0\n"
+        );
+        assert_eq!(
+            edit_yaml_line("weight: 5  # order\ntitle: A\n", "weight", Some(&value)).unwrap(),
+            "weight: 0  # order\ntitle: A\n"
+        );
+        let value = Yaml::String("a # b".to_owned());
+        assert_eq!(
+            edit_yaml_line("weight: '#1' # order\n", "weight", Some(&value)).unwrap(),
+            "weight: \"a # b\" # order\n"
+        );
+        // A key that cannot be matched on its line is left to the emitter instead of being added again.
+        // 行で一致させられないキーは、もう一度追加せずにエミッタに任せる。
+        assert_eq!(
+            edit_yaml_line("\"wei\\u0067ht\": 5\n", "weight", Some(&value)),
+            None
+        );
+        assert_eq!(
+            edit_yaml_line("weight: |\n  5\n", "weight", Some(&value)),
+            None
+        );
+    }
+
+    #[test]
+    fn save_keeps_quoted_key_with_comment() {
+        let dir = TempDir::new().unwrap();
+        let path = write(&dir, "a.md", "---\n\"weight\": 5 # order\n---\nBody\n");
+        let mut page_list = PageList::try_new(&config(dir.path(), &[])).unwrap();
+        page_list.substitute_value().unwrap();
+        page_list.overwrite_frontmatter().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "---\n\"weight\": 0 # order\n---\nBody\n"
+        );
+    }
+
+    #[test]
+    fn load_renumber_summary_counts_pages() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.md", "---\nweight: 5\n---\n");
+        let page_list = PageList::try_new(&config(dir.path(), &[])).unwrap();
+        assert_eq!(
+            page_list.load_renumber_summary().unwrap(),
+            "loading renumbers weight of 1 page: value 5 becomes 0"
+        );
+        write(&dir, "b.md", "---\nweight: 7\n---\n");
+        let page_list = PageList::try_new(&config(dir.path(), &[])).unwrap();
+        assert_eq!(
+            page_list.load_renumber_summary().unwrap(),
+            "loading renumbers weight of 2 pages: values 5..7 become 0..1"
+        );
+        let page_list = PageList::try_new(&config(dir.path(), &["--no-renumber-on-load"])).unwrap();
+        assert_eq!(page_list.load_renumber_summary(), None);
+    }
+
+    #[test]
+    fn pages_without_values_are_listed_in_path_order() {
+        let dir = TempDir::new().unwrap();
+        for name in ["c.md", "a.md", "b.md"] {
+            write(&dir, name, "---\ntitle: T\n---\n");
+        }
+        let page_list = PageList::try_new(&config(dir.path(), &[])).unwrap();
+        assert_eq!(
+            order(&page_list),
+            [
+                entry("a.md", None),
+                entry("b.md", None),
+                entry("c.md", None)
+            ]
+        );
+    }
+
+    #[test]
+    fn pending_changes_lists_changed_excluded_and_included_pages() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.md", "---\nweight: 0\n---\n");
+        write(&dir, "b.md", "---\nweight: 1\n---\n");
+        write(&dir, "c.md", "---\ntitle: C\n---\n");
+        write(&dir, "d.md", "---\nweight: 2\n---\n");
+        let mut page_list = PageList::try_new(&config(dir.path(), &[])).unwrap();
+        assert!(page_list.pending_changes().is_empty());
+        // Exclude b.md, which renumbers d.md, and include c.md after d.md.
+        // b.mdを除外してd.mdの番号を振り直し、c.mdをd.mdの後に含める。
+        page_list.toggle_value(1).unwrap();
+        page_list.toggle_value(3).unwrap();
+        let mut change_list = page_list
+            .pending_changes()
+            .into_iter()
+            .map(|change| {
+                (
+                    change
+                        .path
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned(),
+                    change.key.to_owned(),
+                    change.old_value,
+                    change.new_value,
+                )
+            })
+            .collect::<Vec<_>>();
+        change_list.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            change_list,
+            [
+                (
+                    "b.md".to_owned(),
+                    "weight".to_owned(),
+                    1.into(),
+                    serde_json::Value::Null
+                ),
+                (
+                    "c.md".to_owned(),
+                    "weight".to_owned(),
+                    serde_json::Value::Null,
+                    2.into()
+                ),
+                ("d.md".to_owned(), "weight".to_owned(), 2.into(), 1.into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn unreadable_values_are_listed_and_loading_continues() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.md", "---\nweight: 0\n---\n");
+        write(&dir, "b.md", "---\nweight: abc\n---\n");
+        write(&dir, "c.md", "---\nweight: [1, 2]\n---\n");
+        write(&dir, "d.md", "---\nweight: 1\n---\n");
+        let page_list = PageList::try_new(&config(dir.path(), &[])).unwrap();
+        assert_eq!(
+            order(&page_list),
+            [entry("a.md", Some(0)), entry("d.md", Some(1))]
+        );
+        assert_eq!(
+            page_list.unreadable_list(),
+            &[
+                format!(
+                    "weight: expected integer, found 'abc' in {}",
+                    dir.path().join("b.md").display()
+                ),
+                format!(
+                    "weight: expected integer, found Array in {}",
+                    dir.path().join("c.md").display()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn tiers_are_numbered_from_their_base_values() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.md", "---\nweight: 0\n---\n");
+        write(&dir, "b.md", "---\nweight: 7\n---\n");
+        write(&dir, "c.md", "---\nweight: 100\n---\n");
+        write(&dir, "d.md", "---\nweight: 250\n---\n");
+        write(&dir, "e.md", "---\nweight: 3\n---\n");
+        let page_list =
+            PageList::try_new(&config(dir.path(), &["--tier", "featured=0,normal=100"])).unwrap();
+        assert_eq!(
+            order(&page_list),
+            [
+                entry("a.md", Some(0)),
+                entry("e.md", Some(1)),
+                entry("b.md", Some(2)),
+                entry("c.md", Some(100)),
+                entry("d.md", Some(101)),
+            ]
+        );
+        // A tier with more pages than its range runs over, and the next tier follows it.
+        // 範囲より多くのページを持つ階層ははみ出し、次の階層はその後に続く。
+        write(&dir, "f.md", "---\nweight: 0\n---\n");
+        write(&dir, "g.md", "---\nweight: 1\n---\n");
+        let page_list =
+            PageList::try_new(&config(dir.path(), &["--tier", "featured=0,normal=2"])).unwrap();
+        assert_eq!(
+            order(&page_list),
+            [
+                entry("a.md", Some(0)),
+                entry("f.md", Some(1)),
+                entry("g.md", Some(2)),
+                entry("e.md", Some(3)),
+                entry("b.md", Some(4)),
+                entry("c.md", Some(5)),
+                entry("d.md", Some(6)),
+            ]
+        );
+    }
+
+    #[test]
+    fn next_tier_moves_page_to_end_of_next_tier() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.md", "---\nweight: 0\n---\n");
+        write(&dir, "b.md", "---\nweight: 1\n---\n");
+        write(&dir, "c.md", "---\nweight: 100\n---\n");
+        write(&dir, "d.md", "---\nweight: 101\n---\n");
+        let mut page_list =
+            PageList::try_new(&config(dir.path(), &["--tier", "featured=0,normal=100"])).unwrap();
+        page_list.next_tier(0).unwrap();
+        assert_eq!(
+            order(&page_list),
+            [
+                entry("b.md", Some(0)),
+                entry("c.md", Some(100)),
+                entry("d.md", Some(101)),
+                entry("a.md", Some(102)),
+            ]
+        );
+        assert_eq!(page_list.tier_name(&page_list[3]), Some("normal"));
+        // The last tier is followed by the first one.
+        // 最後の階層の次は最初の階層である。
+        page_list.next_tier(3).unwrap();
+        assert_eq!(
+            order(&page_list),
+            [
+                entry("b.md", Some(0)),
+                entry("a.md", Some(1)),
+                entry("c.md", Some(100)),
+                entry("d.md", Some(101)),
+            ]
+        );
+        assert_eq!(page_list.tier_name(&page_list[1]), Some("featured"));
+    }
+
+    #[test]
+    fn pinned_pages_stay_in_their_tiers() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.md", "---\nweight: 0\n---\n");
+        write(&dir, "b.md", "---\nweight: 1\npinned: last\n---\n");
+        write(&dir, "c.md", "---\nweight: 150\npinned: first\n---\n");
+        write(&dir, "d.md", "---\nweight: 100\n---\n");
+        write(&dir, "e.md", "---\nweight: 2\n---\n");
+        let mut page_list =
+            PageList::try_new(&config(dir.path(), &["--tier", "featured=0,normal=100"])).unwrap();
+        let expected = [
+            entry("a.md", Some(0)),
+            entry("e.md", Some(1)),
+            entry("b.md", Some(2)),
+            entry("c.md", Some(100)),
+            entry("d.md", Some(101)),
+        ];
+        assert_eq!(order(&page_list), expected);
+        assert!(page_list.next_tier(2).is_err());
+        assert_eq!(order(&page_list), expected);
+    }
+
+    #[test]
+    fn tier_near_largest_integer_reports_overflow() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.md", "---\nweight: 9223372036854775806\n---\n");
+        write(&dir, "b.md", "---\nweight: 9223372036854775807\n---\n");
+        write(&dir, "c.md", "---\nweight: 9223372036854775807\n---\n");
+        let err = PageList::try_new(&config(
+            dir.path(),
+            &["--tier", "low=0,high=9223372036854775806"],
+        ))
+        .unwrap_err();
+        assert_eq!(err.to_string(), PageList::OVERFLOW_MESSAGE);
+    }
+
+    #[test]
+    fn one_based_keeps_values_below_one_apart() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "a.md", "---\nweight: 1\n---\n");
+        write(&dir, "b.md", "---\nweight: 0\n---\n");
+        let arg_list = ["--one-based", "--no-renumber-on-load"];
+        let page_list = PageList::try_new(&config(dir.path(), &arg_list)).unwrap();
+        assert_eq!(
+            order(&page_list),
+            [entry("b.md", Some(-1)), entry("a.md", Some(0))]
+        );
+        assert_eq!(
+            page_list.below_one_summary(),
+            format!("0\t{}\n", dir.path().join("b.md").display())
+        );
+        let page_list = PageList::try_new(&config(dir.path(), &["--no-renumber-on-load"])).unwrap();
+        assert_eq!(page_list.below_one_summary(), "");
+        // The note of renumbering on load shows the values as they are in files.
+        // 読み込み時の番号の振り直しの通知は、ファイル上の値で示す。
+        let page_list = PageList::try_new(&config(dir.path(), &["--one-based"])).unwrap();
+        assert_eq!(
+            page_list.load_renumber_summary().unwrap(),
+            "loading renumbers weight of 2 pages: values 0..1 become 1..2"
+        );
+    }
 }
@@ -0,0 +1,64 @@
+// Copyright (c) 2023 Yuichi Ishida
+//
+// Released under the MIT license.
+// see https://opensource.org/licenses/mit-license.php
+
+use crate::cli::Cli;
+use crate::config::Config;
+use crate::page::PageList;
+use clap::Parser;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Settings resolved from the command line as for `--key weight --target <TARGET> --reset-view`, followed by more arguments.
+/// The layout kept by the user is not read.
+/// `--key weight --target <TARGET> --reset-view`に引数を続けたコマンドラインから決めた設定。
+/// ユーザーが保持しているレイアウトは読まない。
+pub(crate) fn config(target: &Path, arg_list: &[&str]) -> Config {
+    let target = target.as_os_str().to_owned();
+    let full_arg_list = [
+        env!("CARGO_PKG_NAME").into(),
+        "--key".into(),
+        "weight".into(),
+        "--target".into(),
+        target,
+        "--reset-view".into(),
+    ]
+    .into_iter()
+    .chain(arg_list.iter().map(Into::into));
+    Cli::try_parse_from(full_arg_list)
+        .unwrap()
+        .config()
+        .unwrap()
+}
+
+/// Write a file under a temporary directory and return its path.
+/// 一時ディレクトリの下にファイルを書き込み、そのパスを返す。
+pub(crate) fn write(dir: &TempDir, name: &str, content: &str) -> PathBuf {
+    let path = dir.path().join(name);
+    fs::write(&path, content).unwrap();
+    path
+}
+
+/// File names and values of pages in the order of the list.
+/// リストの順序でのページのファイル名と値。
+pub(crate) fn order(page_list: &PageList) -> Vec<(String, Option<i64>)> {
+    page_list
+        .iter()
+        .map(|page| {
+            (
+                page.path()
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned(),
+                *page.value(),
+            )
+        })
+        .collect()
+}
+
+pub(crate) fn entry(file_name: &str, value: Option<i64>) -> (String, Option<i64>) {
+    (file_name.to_owned(), value)
+}